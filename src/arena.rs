@@ -0,0 +1,48 @@
+use crate::cleanup::DespawnOnExit;
+use crate::level::{Level, WallShape};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+pub struct ArenaPlugin;
+
+/// This plugin walls in the arena that patients and pills fly around in
+/// The arena's size and walls come from the [`Level`]
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ArenaSize>()
+            .add_system(spawn_walls.in_schedule(OnEnter(GameState::Playing)));
+    }
+}
+
+/// Width and height of the arena in world units. The arena is centered on the origin.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct ArenaSize(pub Vec2);
+
+impl Default for ArenaSize {
+    fn default() -> Self {
+        ArenaSize(Vec2::new(800., 600.))
+    }
+}
+
+impl ArenaSize {
+    pub fn half(&self) -> Vec2 {
+        self.0 / 2.
+    }
+}
+
+fn spawn_walls(mut commands: Commands, level: Res<Level>) {
+    for wall in &level.walls {
+        let collider = match wall.shape {
+            WallShape::Cuboid(half_size) => Collider::cuboid(half_size.x, half_size.y),
+            WallShape::Ball(radius) => Collider::ball(radius),
+        };
+        commands
+            .spawn(DespawnOnExit(GameState::Playing))
+            .insert(collider)
+            .insert(TransformBundle::from(
+                Transform::from_translation(wall.position.extend(0.))
+                    .with_rotation(Quat::from_rotation_z(wall.angle.to_radians())),
+            ));
+    }
+}
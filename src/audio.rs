@@ -1,5 +1,5 @@
 use crate::loading::AudioAssets;
-use crate::pill::spawn_pills;
+use crate::pill::queue_pills;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
@@ -12,9 +12,10 @@ impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(AudioPlugin)
             .add_system(start_audio.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(stop_audio.in_schedule(OnExit(GameState::Playing)))
             .add_system(
                 control_flying_sound
-                    .after(spawn_pills)
+                    .after(queue_pills)
                     .in_set(OnUpdate(GameState::Playing)),
             );
     }
@@ -39,6 +40,10 @@ fn start_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Re
     });
 }
 
+fn stop_audio(audio: Res<Audio>) {
+    audio.stop();
+}
+
 fn control_flying_sound(
     // actions: Res<Actions>,
     audio: Res<FlyingAudio>,
@@ -1,181 +1,581 @@
+use crate::cleanup::DespawnOnExit;
+use crate::level::Level;
 use crate::loading::TextureAssets;
 use crate::GameState;
-use bevy::input::mouse::MouseButtonInput;
-
-use bevy::input::touch::TouchInput;
 use bevy::prelude::*;
 use bevy::sprite::MaterialMesh2dBundle;
+use bevy::utils::{HashMap, HashSet};
 use bevy::window::PrimaryWindow;
-use bevy::window::Window;
+use bevy_rapier2d::prelude::RapierConfiguration;
+use std::f32::consts::{PI, TAU};
 
-use crate::pill::SpawnPillEvent;
+use crate::pill::{PillKind, SpawnPillEvent};
+use crate::pointer::{GameView, PointerEvent, PointerId, PointerPhase};
+use crate::replay::ReplayPlayback;
+use crate::tuning::{AimTuning, Tuning};
 
 #[derive(Component)]
-pub struct Beaker;
+pub struct Beaker {
+    /// Kinds of pills this beaker pops out
+    pub pills: Vec<PillKind>,
+    /// Size of the beaker's texture, before scaling
+    size: Vec2,
+    /// Where the level points the beaker, in radians. Aiming turns it only so far from here.
+    rest_angle: f32,
+}
+
+impl Beaker {
+    /// Rotation of the beaker when nobody is aiming it
+    fn rest_rotation(&self) -> Quat {
+        Quat::from_rotation_z(self.rest_angle)
+    }
+
+    /// The closest launch to `launch` that the beaker can fire: turned only so far, and neither too slow nor too fast
+    fn limit_launch(&self, launch: Vec2, tuning: &AimTuning) -> Vec2 {
+        let max_turn = tuning.max_turn.to_radians();
+        let turn = ((angle_of(launch) - self.rest_angle + PI).rem_euclid(TAU) - PI)
+            .clamp(-max_turn, max_turn);
+        let speed = launch.length().clamp(tuning.min_speed, tuning.max_speed);
+        Vec2::from_angle(self.rest_angle + turn).rotate(Vec2::Y) * speed
+    }
+
+    /// How far the point is from the beaker's outline, or 0 if it is on the beaker
+    fn distance_to(&self, transform: &GlobalTransform, point: Vec2) -> f32 {
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+        let local = (rotation.inverse() * (point.extend(0.) - translation)).truncate();
+        let half_size = self.size * scale.truncate() / 2.;
+        (local.abs() - half_size).max(Vec2::ZERO).length()
+    }
+}
+
+/// The beaker a tap at the given point is meant for: the one it hits or, failing that, the closest one
+/// within `margin`. Where beakers overlap, the one whose middle is closest wins.
+fn beaker_at<'a, T>(
+    beakers: impl IntoIterator<Item = (T, &'a Beaker, &'a GlobalTransform)>,
+    point: Vec2,
+    margin: f32,
+) -> Option<T> {
+    beakers
+        .into_iter()
+        .map(|(target, beaker, transform)| {
+            let outside = beaker.distance_to(transform, point);
+            let to_middle = transform.translation().truncate().distance(point);
+            (target, outside, to_middle)
+        })
+        .filter(|(_, outside, _)| *outside <= margin)
+        .min_by(|(_, a_outside, a_middle), (_, b_outside, b_middle)| {
+            a_outside
+                .total_cmp(b_outside)
+                .then(a_middle.total_cmp(b_middle))
+        })
+        .map(|(target, _, _)| target)
+}
+
+/// Angle that turns a beaker from pointing straight up to pointing along `dir`
+fn angle_of(dir: Vec2) -> f32 {
+    (-dir.x).atan2(dir.y)
+}
+
+/// Launch velocity for a drag between the two points, or nothing if the drag is too short to aim
+fn drag_launch(from: Vec2, to: Vec2, tuning: &AimTuning) -> Option<Vec2> {
+    let drag = to - from;
+    if drag.length() < tuning.min_drag {
+        return None;
+    }
+    Some(drag / tuning.full_drag * tuning.max_speed)
+}
+
+/// Launch velocity for a pointer let go of at the given velocity, or nothing if it wasn't a fling.
+/// Only swipes that got far enough to aim count, so that shaky taps don't fling.
+/// The fling isn't limited here: `handle_taps` holds it to the beaker's turn limit and the aim's `max_speed`,
+/// just like an aim, so a swipe past the turn limit fires along its edge.
+fn fling_launch(drag: Vec2, velocity: Vec2, tuning: &Tuning) -> Option<Vec2> {
+    let fling = &tuning.fling;
+    (drag.length() >= tuning.aim.min_drag && velocity.length() >= fling.min_swipe_speed)
+        .then_some(velocity * fling.speed_factor)
+}
+
+/// Where a pill launched from `start` is after the given number of seconds
+fn flight_path(start: Vec2, velocity: Vec2, gravity: Vec2, seconds: f32) -> Vec2 {
+    start + velocity * seconds + gravity * seconds * seconds / 2.
+}
 
 pub struct BeakerPlugin;
 
-struct TapEvent(Vec2);
+/// A tap or click somewhere in the world
+pub(crate) struct TapEvent {
+    pub pos: Vec2,
+    /// Velocity to launch the pill at, if the tap was dragged out to aim
+    pub launch: Option<Vec2>,
+    /// The beaker the tap was aimed with. Without one, the beaker at `pos` fires.
+    pub beaker: Option<Entity>,
+}
+
+impl TapEvent {
+    pub fn at(pos: Vec2) -> Self {
+        TapEvent {
+            pos,
+            launch: None,
+            beaker: None,
+        }
+    }
+}
+
+/// Beakers that are being aimed, by the pointer aiming them
+#[derive(Resource, Default)]
+pub(crate) struct Aims(HashMap<PointerId, Aim>);
+
+struct Aim {
+    beaker: Entity,
+    /// Velocity the pill gets when the pointer lets go, already within the beaker's limits
+    launch: Option<Vec2>,
+}
+
+/// Dot in the preview of an aimed pill's path
+#[derive(Component)]
+pub(crate) struct AimDot {
+    /// The pointer aiming
+    pointer: PointerId,
+    /// Which dot along the path this is, counting from 1
+    step: u32,
+}
+
+/// Above the beakers
+const AIM_DOT_Z: f32 = 3.;
+
+/// Taps are first collected from input, then turned into pills
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub(crate) enum TapSet {
+    Input,
+    Fire,
+}
 
 impl Plugin for BeakerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(spawn_beakers.in_schedule(OnEnter(GameState::Playing)))
-            .add_system(handle_taps.in_set(OnUpdate(GameState::Playing)))
-            .add_system(handle_clicks_and_touches.in_set(OnUpdate(GameState::Playing)))
-            .add_system(handle_beaker_hover.in_set(OnUpdate(GameState::Playing)))
+        app.configure_set(TapSet::Input.before(TapSet::Fire))
+            .add_system(spawn_beakers.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(
+                handle_taps
+                    .in_set(OnUpdate(GameState::Playing))
+                    .in_set(TapSet::Fire),
+            )
+            .init_resource::<Aims>()
+            .add_system(drop_aims.in_schedule(OnExit(GameState::Playing)))
+            .add_system(
+                aim_beakers
+                    .in_set(OnUpdate(GameState::Playing))
+                    .in_set(TapSet::Input)
+                    .run_if(not(resource_exists::<ReplayPlayback>())),
+            )
+            .add_system(
+                show_aim_previews
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(TapSet::Input)
+                    .run_if(any_with_component::<PrimaryWindow>()),
+            )
+            .add_system(
+                handle_beaker_hover
+                    .in_set(OnUpdate(GameState::Playing))
+                    .run_if(any_with_component::<PrimaryWindow>()),
+            )
+            .add_system(
+                retune_beakers
+                    .in_set(OnUpdate(GameState::Playing))
+                    .run_if(resource_changed::<Tuning>()),
+            )
             .add_event::<TapEvent>();
     }
 }
 
-const BEAKER_SCALE: f32 = 0.4;
-const BEAKER_CLICK_DIST: f32 = 100.;
+/// Purple box that shows where taps hit a beaker, if the tuning file asks for it
+#[derive(Component)]
+struct BeakerHitArea;
+
+/// Size of a beaker's hit area in the beaker's own, unscaled units
+fn hit_area_scale(beaker: &Beaker, tuning: &Tuning) -> Vec3 {
+    let margin = tuning.beaker.tap_margin / tuning.beaker.scale;
+    (beaker.size + 2. * margin).extend(1.)
+}
+
+fn hit_area_visibility(tuning: &Tuning) -> Visibility {
+    if tuning.beaker.show_hit_areas {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
 
 fn spawn_beakers(
     mut commands: Commands,
     textures: Res<TextureAssets>,
-    window_q: Query<&Window, With<PrimaryWindow>>,
+    level: Res<Level>,
+    tuning: Res<Tuning>,
+    images: Res<Assets<Image>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
     let text = textures.folder.get("textures/beaker.png").unwrap();
-    let window = window_q.single();
-
-    let margin: f32 = 30.;
-    let x: f32 = window.resolution.width() / 2. - margin;
-    let y: f32 = window.resolution.height() / 2. - margin;
-    let z: f32 = 2.;
-    let r: f32 = 30.;
-
-    let pos = vec![
-        // Left side, bottom-to-top
-        (-x, -y, z, -r / 2.),
-        (-x, 0., z, -r),
-        (-x, y, z, -r * 2.),
-        // Right side, bottom-to-top
-        (x, -y, z, r / 2.),
-        (x, 0., z, r),
-        (x, y, z, r * 2.),
-    ];
-
-    for pos in pos {
-        let mut transform = Transform::from_xyz(pos.0, pos.1, pos.2)
-            .with_scale(Vec3::new(BEAKER_SCALE, BEAKER_SCALE, 1.))
-            .with_rotation(Quat::from_rotation_z((pos.3).to_radians()));
-        commands.spawn(Beaker).insert(SpriteBundle {
-            texture: text.clone(),
-            transform,
-            ..Default::default()
-        });
+    let size = images.get(text).unwrap().size();
 
-        transform.translation.z = 0.9;
-        commands.spawn(MaterialMesh2dBundle {
-            mesh: meshes
-                .add(shape::Circle::new(BEAKER_CLICK_DIST / BEAKER_SCALE).into())
-                .into(),
-            material: materials.add(ColorMaterial::from(Color::PURPLE)),
-            transform,
-            ..default()
-        });
+    let scale = tuning.beaker.scale;
+    // the hit areas are unit squares, scaled up to the beaker plus the tap margin
+    let hit_area = meshes.add(shape::Quad::new(Vec2::ONE).into());
+    let hit_area_material = materials.add(ColorMaterial::from(Color::PURPLE));
+    for def in &level.beakers {
+        let beaker = Beaker {
+            pills: def.pill_kinds(),
+            size,
+            rest_angle: def.angle.to_radians(),
+        };
+        let hit_area_transform =
+            Transform::from_xyz(0., 0., -1.1).with_scale(hit_area_scale(&beaker, &tuning));
+        let transform = Transform::from_translation(def.position.extend(2.))
+            .with_scale(Vec3::new(scale, scale, 1.))
+            .with_rotation(Quat::from_rotation_z(def.angle.to_radians()));
+        commands
+            .spawn(beaker)
+            .insert(DespawnOnExit(GameState::Playing))
+            .insert(SpriteBundle {
+                texture: text.clone(),
+                transform,
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                parent.spawn(BeakerHitArea).insert(MaterialMesh2dBundle {
+                    mesh: hit_area.clone().into(),
+                    material: hit_area_material.clone(),
+                    transform: hit_area_transform,
+                    visibility: hit_area_visibility(&tuning),
+                    ..default()
+                });
+            });
+    }
+}
+
+fn retune_beakers(
+    tuning: Res<Tuning>,
+    mut beakers: Query<(&Beaker, &mut Transform, &Children), Without<BeakerHitArea>>,
+    mut hit_areas: Query<(&mut Transform, &mut Visibility), With<BeakerHitArea>>,
+) {
+    let scale = tuning.beaker.scale;
+    for (beaker, mut transform, children) in beakers.iter_mut() {
+        transform.scale = Vec3::new(scale, scale, 1.);
+        for child in children.iter() {
+            if let Ok((mut transform, mut visibility)) = hit_areas.get_mut(*child) {
+                transform.scale = hit_area_scale(beaker, &tuning);
+                *visibility = hit_area_visibility(&tuning);
+            }
+        }
     }
 }
 
 fn handle_beaker_hover(
-    primary_window: Query<&Window, With<PrimaryWindow>>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
+    view: GameView,
     textures: Res<TextureAssets>,
-    mut beakers: Query<(&GlobalTransform, &mut Handle<Image>), With<Beaker>>,
+    tuning: Res<Tuning>,
+    mut beakers: Query<(Entity, &Beaker, &GlobalTransform, &mut Handle<Image>)>,
 ) {
-    let window = primary_window.single();
-    let (camera, camera_transform) = camera_q.single();
-    if let Some(world_position) = window
-        .cursor_position()
-        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
-        .map(|ray| ray.origin.truncate())
-    {
-        for (transform, mut texture) in beakers.iter_mut() {
-            if transform.translation().truncate().distance(world_position) < BEAKER_CLICK_DIST {
-                *texture = textures
-                    .folder
-                    .get("textures/beaker_hover.png")
-                    .unwrap()
-                    .clone();
+    if let Some(world_position) = view.cursor() {
+        let hovered = beaker_at(
+            beakers
+                .iter()
+                .map(|(entity, beaker, transform, _)| (entity, beaker, transform)),
+            world_position,
+            tuning.beaker.tap_margin,
+        );
+        for (entity, _, _, mut texture) in beakers.iter_mut() {
+            let path = if Some(entity) == hovered {
+                "textures/beaker_hover.png"
             } else {
-                *texture = textures.folder.get("textures/beaker.png").unwrap().clone();
-            }
+                "textures/beaker.png"
+            };
+            *texture = textures.folder.get(path).unwrap().clone();
         }
     }
 }
 
-fn handle_clicks_and_touches(
-    mut touch_evr: EventReader<TouchInput>,
-    mut mousebtn_evr: EventReader<MouseButtonInput>,
-    primary_window: Query<&Window, With<PrimaryWindow>>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
+fn aim_beakers(
+    mut pointer_events: EventReader<PointerEvent>,
+    mut aims: ResMut<Aims>,
+    mut beakers: Query<(Entity, &Beaker, &GlobalTransform, &mut Transform)>,
+    tuning: Res<Tuning>,
     mut ev_taps: EventWriter<TapEvent>,
 ) {
-    let window = primary_window.single();
-    let (camera, camera_transform) = camera_q.single();
-
-    use bevy::input::ButtonState;
-    for ev in mousebtn_evr.iter() {
-        match ev.state {
-            ButtonState::Pressed => {
-                // println!("Mouse button press: {:?}", ev.button);
+    for event in pointer_events.iter() {
+        match event.phase {
+            PointerPhase::Press => {
+                let beaker = beaker_at(
+                    beakers
+                        .iter()
+                        .map(|(entity, beaker, transform, _)| (entity, beaker, transform)),
+                    event.start,
+                    tuning.beaker.tap_margin,
+                );
+                if let Some(beaker) = beaker {
+                    aims.0.insert(
+                        event.pointer,
+                        Aim {
+                            beaker,
+                            launch: None,
+                        },
+                    );
+                }
             }
-            ButtonState::Released => {
-                // println!("Mouse button release: {:?}", ev.button);
-                if let Some(world_position) = window
-                    .cursor_position()
-                    .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
-                    .map(|ray| ray.origin.truncate())
+            PointerPhase::Drag => {
+                let Some(aim) = aims.0.get_mut(&event.pointer) else {
+                    continue;
+                };
+                let Ok((_, beaker, _, mut transform)) = beakers.get_mut(aim.beaker) else {
+                    continue;
+                };
+                aim.launch = drag_launch(event.start, event.position, &tuning.aim)
+                    .map(|launch| beaker.limit_launch(launch, &tuning.aim));
+                // dragging back to the beaker takes the aim back
+                transform.rotation = match aim.launch {
+                    Some(launch) => Quat::from_rotation_z(angle_of(launch)),
+                    None => beaker.rest_rotation(),
+                };
+            }
+            PointerPhase::Release => {
+                let aim = aims.0.remove(&event.pointer);
+                let fling = fling_launch(event.position - event.start, event.velocity, &tuning);
+                // a swipe off a beaker flings, dragging and letting go fires where the drag aimed
+                let launch = aim.as_ref().and_then(|aim| fling.or(aim.launch));
+                let beaker = aim.map(|aim| aim.beaker);
+                if let Some(Ok((_, beaker, _, mut transform))) =
+                    beaker.map(|entity| beakers.get_mut(entity))
+                {
+                    transform.rotation = beaker.rest_rotation();
+                }
+                // fingers slide a little while tapping, so the spot they went down on counts
+                ev_taps.send(TapEvent {
+                    pos: event.start,
+                    launch,
+                    beaker,
+                });
+            }
+            PointerPhase::Cancel => {
+                let aim = aims.0.remove(&event.pointer);
+                if let Some(Ok((_, beaker, _, mut transform))) =
+                    aim.map(|aim| beakers.get_mut(aim.beaker))
                 {
-                    ev_taps.send(TapEvent(world_position));
+                    transform.rotation = beaker.rest_rotation();
                 }
             }
         }
     }
+}
 
-    for touch in touch_evr.iter() {
-        match touch.phase {
-            bevy::input::touch::TouchPhase::Started => {
-                // ev_taps.send(TapEvent(touch.position));
-                info!("Tap @ {:?}", touch.position);
-                info!(
-                    "Window pos: {:?}, size: {:?}",
-                    window.position,
-                    (window.width(), window.height())
-                );
-
-                // do touches have to be translated into world position like mouse clicks?
-
-                if let Some(mut world_position) = camera
-                    .viewport_to_world(camera_transform, touch.position)
-                    .map(|ray| ray.origin.truncate())
-                {
-                    info!("Tap @ world_position {:?}", world_position);
+/// Stops every aim without firing, e.g. when the run ends or the game pauses
+pub(crate) fn drop_aims(
+    mut commands: Commands,
+    mut aims: ResMut<Aims>,
+    mut beakers: Query<(&Beaker, &mut Transform)>,
+    dots: Query<Entity, With<AimDot>>,
+) {
+    for aim in aims.0.values() {
+        if let Ok((beaker, mut transform)) = beakers.get_mut(aim.beaker) {
+            transform.rotation = beaker.rest_rotation();
+        }
+    }
+    aims.0.clear();
+    for dot in dots.iter() {
+        commands.entity(dot).despawn();
+    }
+}
 
-                    // why is it flipped???
-                    world_position.y = -world_position.y;
+/// Spawns the dots of a preview when an aim starts and despawns them when it ends.
+/// In between, only the dots on the path of an aimed beaker are shown.
+fn show_aim_previews(
+    mut commands: Commands,
+    aims: Res<Aims>,
+    beakers: Query<&GlobalTransform, With<Beaker>>,
+    mut dots: Query<(Entity, &AimDot, &mut Transform, &mut Visibility)>,
+    rapier_config: Res<RapierConfiguration>,
+    tuning: Res<Tuning>,
+) {
+    let tuning = &tuning.aim;
+    let dot_position = |dot: &AimDot| {
+        let aim = aims.0.get(&dot.pointer)?;
+        let launch = aim.launch?;
+        let start = beakers.get(aim.beaker).ok()?.translation().truncate();
+        let seconds = dot.step as f32 / tuning.preview_dots as f32 * tuning.preview_seconds;
+        Some(flight_path(start, launch, rapier_config.gravity, seconds))
+    };
 
-                    ev_taps.send(TapEvent(world_position));
-                }
+    let mut previewed = HashSet::new();
+    for (entity, dot, mut transform, mut visibility) in dots.iter_mut() {
+        if !aims.0.contains_key(&dot.pointer) {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        previewed.insert(dot.pointer);
+        match dot_position(dot) {
+            Some(position) => {
+                transform.translation = position.extend(AIM_DOT_Z);
+                *visibility = Visibility::Inherited;
             }
-            _ => {}
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    // aims start out without a launch, so their dots start out hidden
+    for &pointer in aims.0.keys().filter(|pointer| !previewed.contains(pointer)) {
+        for step in 1..=tuning.preview_dots {
+            commands
+                .spawn(AimDot { pointer, step })
+                .insert(DespawnOnExit(GameState::Playing))
+                .insert(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(1., 1., 1., 0.8),
+                        custom_size: Some(Vec2::splat(5.)),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                });
         }
     }
 }
 
 fn handle_taps(
-    beakers: Query<&GlobalTransform, With<Beaker>>,
+    beakers: Query<(Entity, &Beaker, &GlobalTransform)>,
+    tuning: Res<Tuning>,
     mut taps: EventReader<TapEvent>,
     mut ev_spawn_pill: EventWriter<SpawnPillEvent>,
 ) {
     for tap in taps.iter() {
-        for b in beakers.iter() {
-            if b.translation().truncate().distance(tap.0) < BEAKER_CLICK_DIST {
-                let (_scale, dir, pos) = b.to_scale_rotation_translation();
-                ev_spawn_pill.send(SpawnPillEvent { pos, dir });
-            }
-        }
+        // an aimed beaker may have turned away from where the tap started, so it isn't looked up again
+        let target = tap
+            .beaker
+            .or_else(|| beaker_at(beakers.iter(), tap.pos, tuning.beaker.tap_margin));
+        let Some(Ok((_, beaker, transform))) = target.map(|entity| beakers.get(entity)) else {
+            continue;
+        };
+        let pos = transform.translation();
+        // replays and the like aren't held to the limits while aiming, so apply them here as well
+        let velocity = tap
+            .launch
+            .map(|launch| beaker.limit_launch(launch, &tuning.aim));
+        let dir = match velocity {
+            Some(velocity) => Quat::from_rotation_z(angle_of(velocity)),
+            None => beaker.rest_rotation(),
+        };
+        ev_spawn_pill.send(SpawnPillEvent {
+            pos,
+            dir,
+            kinds: beaker.pills.clone(),
+            velocity,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beaker(position: Vec2, degrees: f32) -> (Beaker, GlobalTransform) {
+        let beaker = Beaker {
+            pills: PillKind::ALL.to_vec(),
+            size: Vec2::new(100., 200.),
+            rest_angle: degrees.to_radians(),
+        };
+        let transform = Transform::from_translation(position.extend(0.))
+            .with_rotation(Quat::from_rotation_z(degrees.to_radians()))
+            .with_scale(Vec3::new(0.5, 0.5, 1.));
+        (beaker, transform.into())
+    }
+
+    #[test]
+    fn hit_test_follows_rotation_and_scale() {
+        // 50 wide and 100 high once scaled
+        let (upright, transform) = beaker(Vec2::ZERO, 0.);
+        assert_eq!(upright.distance_to(&transform, Vec2::new(0., 49.)), 0.);
+        assert_eq!(upright.distance_to(&transform, Vec2::new(45., 0.)), 20.);
+
+        // lying on its side, it is 100 wide and 50 high
+        let (lying, transform) = beaker(Vec2::ZERO, 90.);
+        assert!(lying.distance_to(&transform, Vec2::new(45., 0.)) < 1e-3);
+        assert!((lying.distance_to(&transform, Vec2::new(0., 45.)) - 20.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn overlapping_beakers_resolve_to_one() {
+        let left = beaker(Vec2::new(-20., 0.), 0.);
+        let right = beaker(Vec2::new(20., 0.), 0.);
+        let beakers = [("left", &left.0, &left.1), ("right", &right.0, &right.1)];
+
+        // both beakers cover this spot, the right one's middle is closer
+        assert_eq!(beaker_at(beakers, Vec2::new(3., 0.), 0.), Some("right"));
+        // only the left one is within the margin
+        assert_eq!(beaker_at(beakers, Vec2::new(-60., 0.), 20.), Some("left"));
+        assert_eq!(beaker_at(beakers, Vec2::new(-60., 0.), 10.), None);
+    }
+
+    #[test]
+    fn aim_stays_within_limits() {
+        let tuning = AimTuning {
+            max_turn: 45.,
+            min_speed: 100.,
+            max_speed: 500.,
+            ..default()
+        };
+        // a beaker on the left wall, pointing up and to the right
+        let (beaker, _) = beaker(Vec2::ZERO, -30.);
+
+        let launch = beaker.limit_launch(Vec2::new(300., 0.), &tuning);
+        assert!((angle_of(launch).to_degrees() + 75.).abs() < 1e-3);
+        assert!((launch.length() - 300.).abs() < 1e-3);
+
+        // straight down is too far, so the beaker turns as far right as it can
+        let launch = beaker.limit_launch(Vec2::new(0.1, -1000.), &tuning);
+        assert!((angle_of(launch).to_degrees() + 75.).abs() < 1e-3);
+        assert!((launch.length() - 500.).abs() < 1e-3);
+
+        // straight up is fine, but too slow
+        let launch = beaker.limit_launch(Vec2::new(0., 10.), &tuning);
+        assert!(launch.abs_diff_eq(Vec2::new(0., 100.), 1e-3));
+    }
+
+    #[test]
+    fn short_drags_are_taps() {
+        let tuning = AimTuning {
+            min_drag: 10.,
+            full_drag: 200.,
+            max_speed: 600.,
+            ..default()
+        };
+        assert_eq!(drag_launch(Vec2::ZERO, Vec2::new(5., 5.), &tuning), None);
+        assert_eq!(
+            drag_launch(Vec2::ZERO, Vec2::new(100., 0.), &tuning),
+            Some(Vec2::new(300., 0.))
+        );
+    }
+
+    #[test]
+    fn flings_turn_only_as_far_as_aims() {
+        let tuning = Tuning::default();
+        // a beaker pointing straight up, flung straight right, which is further than it turns
+        let (beaker, _) = beaker(Vec2::ZERO, 0.);
+        let swipe = Vec2::new(tuning.fling.min_swipe_speed * 1.2, 0.);
+        let fling = fling_launch(Vec2::new(100., 0.), swipe, &tuning).unwrap();
+
+        let launch = beaker.limit_launch(fling, &tuning.aim);
+        assert!((angle_of(launch).to_degrees() + tuning.aim.max_turn).abs() < 1e-3);
+        assert!((launch.length() - fling.length()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn only_quick_long_swipes_fling() {
+        let tuning = Tuning::default();
+        let fast = Vec2::new(0., tuning.fling.min_swipe_speed * 2.);
+        let far = Vec2::new(0., tuning.aim.min_drag * 2.);
+
+        assert_eq!(
+            fling_launch(far, fast, &tuning),
+            Some(fast * tuning.fling.speed_factor)
+        );
+        assert_eq!(fling_launch(far, fast / 4., &tuning), None);
+        assert_eq!(fling_launch(far / 4., fast, &tuning), None);
     }
 }
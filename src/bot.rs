@@ -0,0 +1,77 @@
+use crate::beaker::{Beaker, TapEvent, TapSet};
+use crate::patient::Patient;
+use crate::GameState;
+use bevy::prelude::*;
+
+pub struct BotPlugin;
+
+/// This plugin lets a bot tap the beakers, e.g. to try out balance changes without playing by hand
+/// The bot only plays while the [`AutoPlay`] resource exists
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            play_for_player
+                .in_set(OnUpdate(GameState::Playing))
+                .in_set(TapSet::Input)
+                .run_if(resource_exists::<AutoPlay>()),
+        );
+    }
+}
+
+/// Players can't tap every frame either, so the bot waits this many seconds between taps
+const TAP_INTERVAL: f32 = 0.4;
+/// Patients closer than this to a beaker's line of fire are worth a shot
+const AIM_TOLERANCE: f32 = 60.;
+
+/// Lets the bot play
+#[derive(Resource)]
+pub struct AutoPlay {
+    timer: Timer,
+}
+
+impl Default for AutoPlay {
+    fn default() -> Self {
+        AutoPlay {
+            timer: Timer::from_seconds(TAP_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Taps the beaker that points most directly at a patient.
+/// The bot aims along the straight line a beaker fires in, so it ignores gravity and the pill kind.
+fn play_for_player(
+    time: Res<Time>,
+    mut auto_play: ResMut<AutoPlay>,
+    beakers: Query<&GlobalTransform, With<Beaker>>,
+    patients: Query<&GlobalTransform, With<Patient>>,
+    mut ev_taps: EventWriter<TapEvent>,
+) {
+    auto_play.timer.tick(time.delta());
+    if !auto_play.timer.just_finished() {
+        return;
+    }
+
+    let best_shot = beakers
+        .iter()
+        .filter_map(|beaker| {
+            let (_scale, rotation, translation) = beaker.to_scale_rotation_translation();
+            let origin = translation.truncate();
+            let aim = (rotation * Vec3::Y).truncate();
+            patients
+                .iter()
+                .filter_map(|patient| {
+                    let to_patient = patient.translation().truncate() - origin;
+                    // patients behind the beaker can't be hit
+                    (to_patient.dot(aim) > 0.).then(|| aim.perp_dot(to_patient).abs())
+                })
+                .min_by(f32::total_cmp)
+                .map(|miss| (origin, miss))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    if let Some((beaker, miss)) = best_shot {
+        if miss < AIM_TOLERANCE {
+            ev_taps.send(TapEvent::at(beaker));
+        }
+    }
+}
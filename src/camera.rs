@@ -0,0 +1,174 @@
+use crate::arena::ArenaSize;
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy::window::{PrimaryWindow, WindowResized, WindowResolution};
+
+pub struct CameraPlugin;
+
+/// This plugin owns the camera, which always shows exactly the arena
+/// However the window is shaped, the arena is scaled to fit and the rest of the window is blacked out
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_camera)
+            .add_system(fit_camera_to_arena)
+            .add_system(spawn_letterbox.run_if(resource_changed::<ArenaSize>()));
+    }
+}
+
+/// The camera that shows the game
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Black bar covering the part of the window next to the arena
+#[derive(Component)]
+struct Letterbox;
+
+/// Above everything in the arena
+const LETTERBOX_Z: f32 = 100.;
+
+// the camera lives for the whole game, so that coming back to the menu doesn't add another one
+fn setup_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default()).insert(MainCamera);
+}
+
+/// World units per logical pixel of a window of the given size, that shows the whole arena as big as it fits.
+/// The camera shows the world this way, centered on the middle of the arena.
+pub fn world_per_pixel(window: Vec2, arena: Vec2) -> f32 {
+    (arena / window).max_element()
+}
+
+/// Turns a spot in logical pixels from the bottom left of the window, where Bevy puts the cursor,
+/// into the world coordinates the camera shows there
+pub fn window_to_world(position: Vec2, window: &WindowResolution, arena: Vec2) -> Vec2 {
+    let size = Vec2::new(window.width(), window.height());
+    (position - size / 2.) * world_per_pixel(size, arena)
+}
+
+/// Beakers and walls stay where the level put them, so the view scales instead
+fn fit_camera_to_arena(
+    arena: Res<ArenaSize>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut resized: EventReader<WindowResized>,
+    mut projections: Query<&mut OrthographicProjection, With<MainCamera>>,
+) {
+    let was_resized = resized.iter().last().is_some();
+    if !(arena.is_changed() || was_resized) {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let size = Vec2::new(window.width(), window.height());
+    for mut projection in projections.iter_mut() {
+        projection.scaling_mode = ScalingMode::WindowSize(1. / world_per_pixel(size, arena.0));
+    }
+}
+
+fn spawn_letterbox(
+    mut commands: Commands,
+    arena: Res<ArenaSize>,
+    letterbox: Query<Entity, With<Letterbox>>,
+) {
+    for entity in letterbox.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // wide enough to cover any window shape
+    let bar = arena.0.max_element() * 10.;
+    let half = arena.half();
+    let bars = [
+        (Vec2::new(-half.x - bar / 2., 0.), Vec2::new(bar, bar)),
+        (Vec2::new(half.x + bar / 2., 0.), Vec2::new(bar, bar)),
+        (Vec2::new(0., -half.y - bar / 2.), Vec2::new(arena.0.x, bar)),
+        (Vec2::new(0., half.y + bar / 2.), Vec2::new(arena.0.x, bar)),
+    ];
+    for (position, size) in bars {
+        commands.spawn(Letterbox).insert(SpriteBundle {
+            sprite: Sprite {
+                color: Color::BLACK,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(LETTERBOX_Z)),
+            ..default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARENA: Vec2 = Vec2::new(800., 600.);
+
+    /// A window of the given size in logical pixels
+    fn window(width: f32, height: f32, scale_factor: f64) -> WindowResolution {
+        let scale = scale_factor as f32;
+        let window = WindowResolution::new(width * scale, height * scale)
+            .with_scale_factor_override(scale_factor);
+        assert_eq!((window.width(), window.height()), (width, height));
+        window
+    }
+
+    #[test]
+    fn window_matching_the_arena_maps_pixels_to_world_units() {
+        let window = window(800., 600., 1.);
+        assert_eq!(
+            window_to_world(Vec2::new(400., 300.), &window, ARENA),
+            Vec2::ZERO
+        );
+        assert_eq!(
+            window_to_world(Vec2::ZERO, &window, ARENA),
+            Vec2::new(-400., -300.)
+        );
+        assert_eq!(
+            window_to_world(Vec2::new(800., 600.), &window, ARENA),
+            Vec2::new(400., 300.)
+        );
+    }
+
+    #[test]
+    fn bigger_window_scales_down() {
+        let window = window(1600., 1200., 1.);
+        assert_eq!(
+            window_to_world(Vec2::new(1600., 0.), &window, ARENA),
+            Vec2::new(400., -300.)
+        );
+    }
+
+    #[test]
+    fn wide_window_letterboxes_the_sides() {
+        // the arena is 600 pixels high, so 800 pixels wide, leaving 200 on either side
+        let window = window(1200., 600., 1.);
+        assert_eq!(
+            window_to_world(Vec2::new(200., 0.), &window, ARENA),
+            Vec2::new(-400., -300.)
+        );
+        assert_eq!(
+            window_to_world(Vec2::new(100., 300.), &window, ARENA),
+            Vec2::new(-500., 0.)
+        );
+    }
+
+    #[test]
+    fn tall_window_letterboxes_top_and_bottom() {
+        // the arena is 400 pixels wide, so 300 pixels high, leaving 150 above and below
+        let window = window(400., 600., 1.);
+        assert_eq!(
+            window_to_world(Vec2::new(400., 450.), &window, ARENA),
+            Vec2::new(400., 300.)
+        );
+    }
+
+    #[test]
+    fn scale_factor_does_not_change_logical_positions() {
+        for scale_factor in [1., 1.5, 2., 3.] {
+            let window = window(800., 600., scale_factor);
+            assert_eq!(
+                window_to_world(Vec2::new(600., 450.), &window, ARENA),
+                Vec2::new(200., 150.),
+                "scale factor {scale_factor}"
+            );
+        }
+    }
+}
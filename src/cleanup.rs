@@ -0,0 +1,37 @@
+use crate::GameState;
+use bevy::prelude::*;
+use std::marker::PhantomData;
+
+pub struct CleanupPlugin<S: States = GameState>(PhantomData<S>);
+
+impl<S: States> Default for CleanupPlugin<S> {
+    fn default() -> Self {
+        CleanupPlugin(PhantomData)
+    }
+}
+
+/// Entities marked with this component are despawned when the game leaves the given state
+#[derive(Component)]
+pub struct DespawnOnExit<S: States = GameState>(pub S);
+
+/// This plugin despawns everything that only belongs to one state, so that
+/// entering that state again starts from a clean slate
+impl<S: States> Plugin for CleanupPlugin<S> {
+    fn build(&self, app: &mut App) {
+        for state in S::variants() {
+            app.add_system(despawn_on_exit(state.clone()).in_schedule(OnExit(state)));
+        }
+    }
+}
+
+fn despawn_on_exit<S: States>(
+    state: S,
+) -> impl FnMut(Commands, Query<(Entity, &DespawnOnExit<S>)>) {
+    move |mut commands: Commands, entities: Query<(Entity, &DespawnOnExit<S>)>| {
+        for (e, despawn_on_exit) in entities.iter() {
+            if despawn_on_exit.0 == state {
+                commands.entity(e).despawn_recursive();
+            }
+        }
+    }
+}
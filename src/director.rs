@@ -0,0 +1,172 @@
+#[cfg(debug_assertions)]
+use crate::cleanup::DespawnOnExit;
+#[cfg(debug_assertions)]
+use crate::loading::FontAssets;
+use crate::patient::{Patient, PatientDiedEvent, PatientHealedEvent, PatientSpawnConfig};
+use crate::pill::SpawnPillEvent;
+use crate::GameState;
+use bevy::prelude::*;
+
+pub struct DirectorPlugin;
+
+/// This plugin watches how well the player is doing and makes the game easier or harder to match
+/// The director is only active during the State `GameState::Playing`
+impl Plugin for DirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Director>()
+            .add_system(reset_director.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(observe_player.in_set(OnUpdate(GameState::Playing)))
+            .add_system(apply_intensity.in_set(OnUpdate(GameState::Playing)));
+
+        #[cfg(debug_assertions)]
+        {
+            app.add_system(
+                setup_director_overlay
+                    .in_schedule(OnEnter(GameState::Playing))
+                    .run_if(resource_exists::<FontAssets>()),
+            )
+            .add_system(update_director_overlay.in_set(OnUpdate(GameState::Playing)));
+        }
+    }
+}
+
+/// How often the director looks at the player's performance, in seconds
+const SAMPLE_PERIOD: f32 = 1.;
+/// How much the newest sample counts towards the smoothed rates
+const SMOOTHING: f32 = 0.3;
+
+/// Bounds for the intensity, and how much it may change per sample
+const MIN_INTENSITY: f32 = 0.6;
+const MAX_INTENSITY: f32 = 1.8;
+const INTENSITY_STEP: f32 = 0.05;
+
+/// More patients than this on screen means the player is struggling
+const CROWDED: u32 = 8;
+/// Fewer patients than this on screen means the player has things under control
+const CALM: u32 = 3;
+
+/// Keeps track of how the player is doing and how hard the game should be because of it
+#[derive(Resource, Debug)]
+pub struct Director {
+    /// Smoothed patients healed per second
+    pub heal_rate: f32,
+    /// Smoothed pills per second that didn't heal anyone
+    pub miss_rate: f32,
+    /// Patients alive right now
+    pub patients_on_screen: u32,
+    /// Multiplies the spawn rate and how hard patients are thrown around.
+    /// Above 1 means more chaos, below 1 means relief.
+    pub intensity: f32,
+    sample_timer: Timer,
+    heals: u32,
+    deaths: u32,
+    pills: u32,
+}
+
+impl Default for Director {
+    fn default() -> Self {
+        Director {
+            heal_rate: 0.,
+            miss_rate: 0.,
+            patients_on_screen: 0,
+            intensity: 1.,
+            sample_timer: Timer::from_seconds(SAMPLE_PERIOD, TimerMode::Repeating),
+            heals: 0,
+            deaths: 0,
+            pills: 0,
+        }
+    }
+}
+
+fn reset_director(mut director: ResMut<Director>) {
+    *director = Director::default();
+}
+
+fn observe_player(
+    time: Res<Time>,
+    mut director: ResMut<Director>,
+    patients: Query<(), With<Patient>>,
+    mut ev_healed: EventReader<PatientHealedEvent>,
+    mut ev_died: EventReader<PatientDiedEvent>,
+    mut ev_spawn_pill: EventReader<SpawnPillEvent>,
+) {
+    director.heals += ev_healed.iter().count() as u32;
+    director.deaths += ev_died.iter().count() as u32;
+    director.pills += ev_spawn_pill.iter().count() as u32;
+    director.patients_on_screen = patients.iter().count() as u32;
+
+    director.sample_timer.tick(time.delta());
+    if !director.sample_timer.just_finished() {
+        return;
+    }
+
+    let heals = director.heals as f32 / SAMPLE_PERIOD;
+    let misses = director.pills.saturating_sub(director.heals) as f32 / SAMPLE_PERIOD;
+    director.heal_rate += (heals - director.heal_rate) * SMOOTHING;
+    director.miss_rate += (misses - director.miss_rate) * SMOOTHING;
+
+    let struggling = director.deaths > 0
+        || director.patients_on_screen > CROWDED
+        || director.miss_rate > 2. * director.heal_rate + 1.;
+    let cruising = director.patients_on_screen < CALM && director.heal_rate > 0.5;
+
+    if struggling {
+        // losing patients hurts, so back off twice as fast
+        let step = if director.deaths > 0 { 2. } else { 1. } * INTENSITY_STEP;
+        director.intensity -= step;
+    } else if cruising {
+        director.intensity += INTENSITY_STEP;
+    }
+    director.intensity = director.intensity.clamp(MIN_INTENSITY, MAX_INTENSITY);
+
+    director.heals = 0;
+    director.deaths = 0;
+    director.pills = 0;
+}
+
+fn apply_intensity(director: Res<Director>, mut config: ResMut<PatientSpawnConfig>) {
+    config.set_intensity(director.intensity);
+}
+
+#[cfg(debug_assertions)]
+#[derive(Component)]
+struct DirectorOverlay;
+
+#[cfg(debug_assertions)]
+fn setup_director_overlay(mut commands: Commands, font_assets: Res<FontAssets>) {
+    commands
+        .spawn(DespawnOnExit(GameState::Playing))
+        .insert(DirectorOverlay)
+        .insert(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: font_assets.fira_sans.clone(),
+                    font_size: 16.0,
+                    color: Color::rgb(0.9, 0.9, 0.2),
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(15.0),
+                    right: Val::Px(100.0),
+                    ..default()
+                },
+                ..default()
+            }),
+        );
+}
+
+#[cfg(debug_assertions)]
+fn update_director_overlay(
+    director: Res<Director>,
+    mut query: Query<&mut Text, With<DirectorOverlay>>,
+) {
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[0].value = format!(
+            "heal rate: {:.2}/s\nmiss rate: {:.2}/s\non screen: {}\nintensity: {:.2}",
+            director.heal_rate, director.miss_rate, director.patients_on_screen, director.intensity
+        );
+    }
+}
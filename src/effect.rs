@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+/// Smallest and largest size a patient can be shrunk or grown to
+const MIN_PATIENT_SCALE: f32 = 0.2;
+const MAX_PATIENT_SCALE: f32 = 1.2;
+
+/// Something a pill does to a patient it doesn't cure
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SideEffect {
+    /// Makes the patient's ailment worse
+    Worsen,
+    /// Multiplies the patient's size
+    Scale(f32),
+    /// Multiplies how strongly gravity pulls on the patient
+    Gravity(f32),
+    /// Kicks the patient into a spin
+    Spin(f32),
+    /// Sets how much the patient bounces off things
+    Bounce(f32),
+}
+
+impl SideEffect {
+    /// Changes the patient's body according to this effect.
+    /// Worsening is about the ailment, not the body, so that is left to the caller.
+    pub fn apply(
+        &self,
+        transform: &mut Transform,
+        gravity: &mut GravityScale,
+        restitution: &mut Restitution,
+        impulse: &mut ExternalImpulse,
+    ) {
+        match *self {
+            SideEffect::Worsen => {}
+            SideEffect::Scale(factor) => {
+                let scale =
+                    (transform.scale.x * factor).clamp(MIN_PATIENT_SCALE, MAX_PATIENT_SCALE);
+                transform.scale = Vec3::new(scale, scale, 1.);
+            }
+            SideEffect::Gravity(factor) => {
+                gravity.0 *= factor;
+            }
+            SideEffect::Spin(torque) => {
+                impulse.torque_impulse += torque;
+            }
+            SideEffect::Bounce(coefficient) => {
+                restitution.coefficient = coefficient;
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::cleanup::DespawnOnExit;
+use crate::loading::FontAssets;
+use crate::menu::{menu_text_style, spawn_menu_button, ButtonAction, ButtonColors};
+use crate::patient::PatientHealedEvent;
+use crate::pill::SpawnPillEvent;
+use crate::rng::GameRng;
+use crate::score::Score;
+use crate::GameState;
+use bevy::prelude::*;
+
+pub struct GameOverPlugin;
+
+/// This plugin keeps track of how a run went and shows the results once it is over
+/// The results are only drawn during the State `GameState::GameOver`
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_system(reset_run_stats.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(track_run_stats.in_set(OnUpdate(GameState::Playing)))
+            .add_system(setup_game_over.in_schedule(OnEnter(GameState::GameOver)));
+    }
+}
+
+/// Numbers shown on the results screen
+#[derive(Resource, Default)]
+pub struct RunStats {
+    pub pills_fired: u32,
+    /// Seconds since the run started
+    pub time_survived: f32,
+    /// Fastest impact of a pill on a patient it healed
+    pub hardest_hit: f32,
+    /// Longest time a pill flew before healing someone, in seconds
+    pub longest_shot: f32,
+}
+
+fn reset_run_stats(mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+fn track_run_stats(
+    time: Res<Time>,
+    mut stats: ResMut<RunStats>,
+    mut ev_healed: EventReader<PatientHealedEvent>,
+    mut ev_spawn_pill: EventReader<SpawnPillEvent>,
+) {
+    stats.time_survived += time.delta_seconds();
+    stats.pills_fired += ev_spawn_pill.iter().count() as u32;
+    for ev in ev_healed.iter() {
+        stats.hardest_hit = stats.hardest_hit.max(ev.relative_velocity.length());
+        stats.longest_shot = stats.longest_shot.max(ev.time_since_fired);
+    }
+}
+
+fn setup_game_over(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    stats: Res<RunStats>,
+    score: Res<Score>,
+    game_rng: Res<GameRng>,
+) {
+    let text_style = menu_text_style(&font_assets);
+
+    commands
+        .spawn(DespawnOnExit(GameState::GameOver))
+        .insert(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "The hospital had to close!",
+                TextStyle {
+                    font_size: 40.0,
+                    ..text_style.clone()
+                },
+            ));
+            parent.spawn(
+                TextBundle::from_section(
+                    format!(
+                        "Score: {}\nPatients saved: {}\nTime survived: {:.0}s\nPills fired: {}\n\
+                         Hardest hit: {:.0}\nLongest shot: {:.1}s\nSeed: {}",
+                        score.points,
+                        score.saved,
+                        stats.time_survived,
+                        stats.pills_fired,
+                        stats.hardest_hit,
+                        stats.longest_shot,
+                        game_rng.seed
+                    ),
+                    text_style.clone(),
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+
+            for (label, action) in [
+                ("Retry", ButtonAction::Play),
+                ("Main menu", ButtonAction::MainMenu),
+            ] {
+                spawn_menu_button(parent, label, action, &font_assets, &button_colors);
+            }
+        });
+}
@@ -0,0 +1,321 @@
+//! Running the game without a window, renderer or sound card, e.g. on CI
+
+use crate::beaker::{Beaker, TapEvent};
+use crate::bot::AutoPlay;
+use crate::level::{Level, LevelName};
+use crate::loading::{parse_ron_asset, TextureAssets};
+use crate::patient::{Ailment, LostPatients, Patient, PatientHealedEvent, PatientSpawnConfig};
+use crate::physics::{Lockstep, FIXED_TIMESTEP};
+use crate::pill::{Pill, PillKind, SpawnPillEvent};
+use crate::pointer::{PointerEvent, PointerId, PointerPhase};
+use crate::replay::RunFrame;
+use crate::rng::RngSeed;
+use crate::score::Score;
+use crate::tuning::{Tuning, TUNING_PATH};
+use crate::{GamePlugin, GameState};
+use bevy::asset::FileAssetIo;
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_rapier2d::prelude::*;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+pub(crate) struct HeadlessPlugin;
+
+/// This plugin stands in for everything the simulation needs from the asset and loading plugins
+/// Instead of the menu, the game goes straight into a run
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<AssetPlugin>() {
+            app.add_plugin(AssetPlugin::default());
+        }
+        if !app.is_plugin_added::<TransformPlugin>() {
+            app.add_plugin(TransformPlugin);
+        }
+        app.add_asset::<Image>()
+            .add_asset::<Mesh>()
+            .add_asset::<ColorMaterial>()
+            .add_startup_system(load_blank_textures)
+            .add_startup_system(load_tuning)
+            .add_startup_system(load_level)
+            .add_startup_system(start_run);
+    }
+}
+
+/// Collision shapes are sized after their textures, so only the size of every texture is read.
+/// The stand-ins are blank images of the same size.
+fn load_blank_textures(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let root = FileAssetIo::get_base_path().join("assets");
+    let paths = Ailment::ALL
+        .iter()
+        .map(|ailment| ailment.texture())
+        .chain(PillKind::ALL.iter().map(|kind| kind.def().texture))
+        .chain(["textures/beaker.png", "textures/beaker_hover.png"]);
+
+    let folder = paths
+        .map(|path| {
+            let (width, height) = image::image_dimensions(root.join(path))
+                .unwrap_or_else(|err| panic!("Can't read {}: {}", path, err));
+            let image = Image::new_fill(
+                Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                &[0; 4],
+                TextureFormat::Rgba8UnormSrgb,
+            );
+            (path.to_string(), images.add(image))
+        })
+        .collect();
+    commands.insert_resource(TextureAssets { folder });
+}
+
+/// Reads a RON file from the assets folder right away, instead of waiting for the asset server
+fn read_ron_asset<T: DeserializeOwned>(path: &str) -> T {
+    let path = FileAssetIo::get_base_path().join("assets").join(path);
+    let bytes =
+        std::fs::read(&path).unwrap_or_else(|err| panic!("Can't read {}: {}", path.display(), err));
+    parse_ron_asset(&path, &bytes).unwrap_or_else(|err| panic!("{}", err))
+}
+
+fn load_tuning(mut tuning: ResMut<Tuning>) {
+    *tuning = read_ron_asset(TUNING_PATH);
+    if let Err(problem) = tuning.check() {
+        panic!("Bad tuning in {}: {}", TUNING_PATH, problem);
+    }
+}
+
+fn load_level(mut commands: Commands, level_name: Res<LevelName>) {
+    let level: Level = read_ron_asset(&level_name.path());
+    level.apply(&mut commands);
+}
+
+fn start_run(mut state: ResMut<NextState<GameState>>) {
+    state.set(GameState::Playing);
+}
+
+/// Radius of the round stand-ins that [`Simulation::spawn_patient`] spawns
+pub const PATIENT_RADIUS: f32 = 30.;
+/// Radius of the round stand-ins that [`Simulation::spawn_pill`] spawns
+pub const PILL_RADIUS: f32 = 8.;
+
+/// A run of the game under `MinimalPlugins`, for tests and bulk simulation.
+/// Every frame advances the game by exactly one physics step.
+pub struct Simulation {
+    pub app: App,
+    healed: Vec<PatientHealedEvent>,
+    heal_reader: ManualEventReader<PatientHealedEvent>,
+    pills_fired: u32,
+    pill_reader: ManualEventReader<SpawnPillEvent>,
+}
+
+impl Simulation {
+    /// Starts a run with the given seed, on the default level
+    pub fn new(seed: u64) -> Self {
+        Simulation::on_level(seed, LevelName::default())
+    }
+
+    /// Starts a run with the given seed, on the given level
+    pub fn on_level(seed: u64, level: LevelName) -> Self {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(GamePlugin { headless: true })
+            .insert_resource(RngSeed(seed))
+            .insert_resource(level)
+            .insert_resource(Lockstep);
+        let mut sim = Simulation {
+            app,
+            healed: Vec::new(),
+            heal_reader: default(),
+            pills_fired: 0,
+            pill_reader: default(),
+        };
+        // the first frame enters the run
+        sim.update();
+        sim
+    }
+
+    /// Taps the given spot in world coordinates, as if the player did. Takes effect on the next frame.
+    pub fn tap(&mut self, pos: Vec2) {
+        self.app.world.send_event(TapEvent::at(pos));
+    }
+
+    /// Drags out from the given spot to aim a pill at the given velocity, as if the player did.
+    /// Takes effect on the next frame.
+    pub fn aim(&mut self, pos: Vec2, launch: Vec2) {
+        self.app.world.send_event(TapEvent {
+            pos,
+            launch: Some(launch),
+            beaker: None,
+        });
+    }
+
+    /// Presses, drags or lets go of the mouse at `position`, after it was pressed at `start`, as if the player did.
+    /// Positions are in world coordinates. Takes effect on the next frame.
+    pub fn point(&mut self, phase: PointerPhase, start: Vec2, position: Vec2) {
+        self.app.world.send_event(PointerEvent {
+            pointer: PointerId::Mouse,
+            phase,
+            position,
+            start,
+            velocity: Vec2::ZERO,
+        });
+    }
+
+    /// Which way the beaker at the given spot points, in degrees counterclockwise from straight up
+    pub fn beaker_angle(&mut self, pos: Vec2) -> f32 {
+        let mut beakers = self.app.world.query_filtered::<&Transform, With<Beaker>>();
+        let transform = beakers
+            .iter(&self.app.world)
+            .find(|transform| transform.translation.truncate().distance(pos) < 1.)
+            .unwrap_or_else(|| panic!("No beaker at {}", pos));
+        transform.rotation.to_euler(EulerRot::ZYX).0.to_degrees()
+    }
+
+    /// Runs the game for the given number of seconds of game time
+    pub fn advance(&mut self, seconds: f32) {
+        self.step(seconds, false);
+    }
+
+    /// Runs one frame per physics step in the given number of seconds, stopping early once the run is over if asked to
+    fn step(&mut self, seconds: f32, until_over: bool) {
+        let frames = (seconds / FIXED_TIMESTEP).round() as u32;
+        for _ in 0..frames {
+            if until_over && self.is_over() {
+                break;
+            }
+            self.update();
+        }
+    }
+
+    fn update(&mut self) {
+        self.app.update();
+        let events = self.app.world.resource::<Events<PatientHealedEvent>>();
+        self.healed.extend(self.heal_reader.iter(events).cloned());
+        let events = self.app.world.resource::<Events<SpawnPillEvent>>();
+        self.pills_fired += self.pill_reader.iter(events).count() as u32;
+    }
+
+    /// Lets the bot from [`AutoPlay`] tap the beakers
+    pub fn autoplay(&mut self) {
+        self.app.world.insert_resource(AutoPlay::default());
+    }
+
+    /// Runs the game for the given number of seconds, or until the run is over
+    pub fn run_for(&mut self, seconds: f32) {
+        self.step(seconds, true);
+    }
+
+    /// Sums up the run so far
+    pub fn report(&self) -> Report {
+        let world = &self.app.world;
+        Report {
+            seconds: world.resource::<RunFrame>().0 as f32 * FIXED_TIMESTEP,
+            pills_fired: self.pills_fired,
+            healed: self.healed.len() as u32,
+            lost: world.resource::<LostPatients>().0,
+            score: world.resource::<Score>().points,
+            wave: world.resource::<PatientSpawnConfig>().wave_number,
+        }
+    }
+
+    /// Every heal since the run started
+    pub fn healed(&self) -> &[PatientHealedEvent] {
+        &self.healed
+    }
+
+    /// Stops the waves, so that only patients spawned with [`Simulation::spawn_patient`] show up
+    pub fn hold_waves(&mut self) {
+        self.app.world.resource_mut::<PatientSpawnConfig>().hold();
+    }
+
+    /// Spawns a round patient that is otherwise just like the ones the waves bring
+    pub fn spawn_patient(&mut self, ailment: Ailment, pos: Vec2) -> Entity {
+        let tuning = self.app.world.resource::<Tuning>().clone();
+        self.app
+            .world
+            .spawn(SpriteBundle {
+                transform: Transform::from_translation(pos.extend(1.)),
+                ..default()
+            })
+            .insert(Patient::body(ailment, &tuning.patient))
+            .insert(Collider::ball(PATIENT_RADIUS))
+            .id()
+    }
+
+    /// Spawns a round single dose pill flying at the given velocity
+    pub fn spawn_pill(&mut self, kind: PillKind, pos: Vec2, velocity: Vec2) -> Entity {
+        let tuning = self.app.world.resource::<Tuning>().clone();
+        self.app
+            .world
+            .spawn(SpriteBundle {
+                transform: Transform::from_translation(pos.extend(1.)),
+                ..default()
+            })
+            .insert(Pill::body(kind, 1, &tuning.pill))
+            .insert(Collider::ball(PILL_RADIUS))
+            .insert(Velocity::linear(velocity))
+            .id()
+    }
+
+    /// Whether the entity is still around, i.e. wasn't despawned
+    pub fn exists(&self, entity: Entity) -> bool {
+        self.app.world.get_entity(entity).is_some()
+    }
+
+    /// Whether too many patients were lost and the run is over
+    pub fn is_over(&self) -> bool {
+        self.app.world.resource::<State<GameState>>().0 == GameState::GameOver
+    }
+}
+
+/// How a simulated run went
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    /// Game time the run lasted
+    pub seconds: f32,
+    pub pills_fired: u32,
+    pub healed: u32,
+    pub lost: u32,
+    pub score: u32,
+    /// The wave the run got to
+    pub wave: u32,
+}
+
+impl Report {
+    /// Heals per minute of game time, or 0 for a run that hasn't started yet
+    pub fn heals_per_minute(&self) -> f32 {
+        if self.seconds <= 0. {
+            return 0.;
+        }
+        self.healed as f32 / self.seconds * 60.
+    }
+
+    /// Share of pills that healed someone
+    pub fn accuracy(&self) -> f32 {
+        self.healed as f32 / self.pills_fired.max(1) as f32
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Time survived: {:.1}s (wave {})",
+            self.seconds, self.wave
+        )?;
+        writeln!(f, "Pills fired: {}", self.pills_fired)?;
+        writeln!(
+            f,
+            "Patients healed: {} ({:.1}/min, {:.0}% of pills)",
+            self.healed,
+            self.heals_per_minute(),
+            self.accuracy() * 100.
+        )?;
+        writeln!(f, "Patients lost: {}", self.lost)?;
+        write!(f, "Score: {}", self.score)
+    }
+}
@@ -0,0 +1,180 @@
+use crate::arena::ArenaSize;
+use crate::cleanup::DespawnOnExit;
+use crate::loading::{parse_ron_asset, LevelAssets};
+use crate::pill::PillKind;
+use crate::GameState;
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use bevy::window::PrimaryWindow;
+use serde::Deserialize;
+
+pub struct LevelPlugin;
+
+/// This plugin loads the level picked by [`LevelName`] and lays out the background from it
+/// Beakers, walls and patients are placed by their own plugins, following the [`Level`] resource
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelName>()
+            .add_asset::<Level>()
+            .add_asset_loader(LevelLoader)
+            .add_system(apply_level.run_if(resource_added::<LevelAssets>()))
+            .add_system(
+                spawn_background
+                    .in_schedule(OnEnter(GameState::Playing))
+                    .run_if(any_with_component::<PrimaryWindow>()),
+            );
+    }
+}
+
+/// Name of the level to play, i.e. the file `assets/levels/<name>.level.ron`
+#[derive(Resource, Clone, Debug)]
+pub struct LevelName(pub String);
+
+impl Default for LevelName {
+    fn default() -> Self {
+        LevelName("hospital".to_string())
+    }
+}
+
+impl LevelName {
+    /// Path of the level file, relative to the assets folder
+    pub fn path(&self) -> String {
+        format!("levels/{}.level.ron", self.0)
+    }
+}
+
+/// Layout of the arena. Positions are in world units, with the origin in the middle of the arena.
+#[derive(Resource, TypeUuid, Deserialize, Clone, Debug)]
+#[uuid = "9e0d3a61-6c1b-4d8e-b7f2-3a4c5d6e7f80"]
+pub struct Level {
+    /// Width and height of the arena
+    pub size: Vec2,
+    /// Texture drawn behind everything, relative to the assets folder
+    #[serde(default)]
+    pub background: Option<String>,
+    pub beakers: Vec<BeakerDef>,
+    /// Walls and obstacles that patients and pills bounce off
+    #[serde(default)]
+    pub walls: Vec<WallDef>,
+    /// Patients show up somewhere in one of these. Without any, they show up all over the arena.
+    #[serde(default)]
+    pub spawn_zones: Vec<SpawnZone>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BeakerDef {
+    pub position: Vec2,
+    /// Degrees counterclockwise from pointing straight up
+    pub angle: f32,
+    /// Kinds of pills the beaker pops out. Left empty, it pops out every kind.
+    #[serde(default)]
+    pub pills: Vec<PillKind>,
+}
+
+impl BeakerDef {
+    pub fn pill_kinds(&self) -> Vec<PillKind> {
+        if self.pills.is_empty() {
+            PillKind::ALL.to_vec()
+        } else {
+            self.pills.clone()
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WallDef {
+    pub position: Vec2,
+    /// Degrees counterclockwise
+    #[serde(default)]
+    pub angle: f32,
+    pub shape: WallShape,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum WallShape {
+    /// A box with the given half width and half height
+    Cuboid(Vec2),
+    /// A circle with the given radius
+    Ball(f32),
+}
+
+/// A rectangle patients can show up in
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct SpawnZone {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl SpawnZone {
+    /// The point at the given fractions of the zone's width and height
+    pub fn lerp(&self, fraction: Vec2) -> Vec2 {
+        self.min + (self.max - self.min) * fraction
+    }
+}
+
+impl Level {
+    /// Where patients can show up
+    pub fn spawn_zones(&self) -> Vec<SpawnZone> {
+        if self.spawn_zones.is_empty() {
+            vec![SpawnZone {
+                min: -self.size / 2.,
+                max: self.size / 2.,
+            }]
+        } else {
+            self.spawn_zones.clone()
+        }
+    }
+
+    /// Makes this the level that gets played
+    pub(crate) fn apply(self, commands: &mut Commands) {
+        commands.insert_resource(ArenaSize(self.size));
+        commands.insert_resource(self);
+    }
+}
+
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level: Level = parse_ron_asset(load_context.path(), bytes)?;
+            let mut asset = LoadedAsset::new(level.clone());
+            // start loading the background along with the level
+            if let Some(background) = level.background {
+                asset = asset.with_dependency(AssetPath::new(background.into(), None));
+            }
+            load_context.set_default_asset(asset);
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+fn apply_level(mut commands: Commands, level_assets: Res<LevelAssets>, levels: Res<Assets<Level>>) {
+    let level = levels.get(&level_assets.level).unwrap().clone();
+    level.apply(&mut commands);
+}
+
+fn spawn_background(mut commands: Commands, level: Res<Level>, asset_server: Res<AssetServer>) {
+    if let Some(background) = &level.background {
+        commands
+            .spawn(DespawnOnExit(GameState::Playing))
+            .insert(SpriteBundle {
+                texture: asset_server.load(background.as_str()),
+                sprite: Sprite {
+                    custom_size: Some(level.size),
+                    ..default()
+                },
+                ..default()
+            });
+    }
+}
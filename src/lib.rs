@@ -1,22 +1,62 @@
+mod arena;
 mod audio;
 mod beaker;
+mod bot;
+mod camera;
+mod cleanup;
+mod director;
+mod effect;
+mod game_over;
+pub mod headless;
+mod level;
 mod loading;
 mod menu;
 mod patient;
+mod pause;
+mod physics;
 mod pill;
+mod pointer;
+mod replay;
+mod rng;
+mod score;
+mod tuning;
 mod ui;
+mod wave;
 
+use crate::arena::ArenaPlugin;
 use crate::audio::InternalAudioPlugin;
 use crate::beaker::BeakerPlugin;
+use crate::bot::BotPlugin;
+use crate::camera::CameraPlugin;
+use crate::cleanup::CleanupPlugin;
+use crate::director::DirectorPlugin;
+use crate::game_over::GameOverPlugin;
+use crate::headless::HeadlessPlugin;
+use crate::level::LevelPlugin;
 use crate::loading::LoadingPlugin;
 use crate::menu::MenuPlugin;
 use crate::patient::PatientPlugin;
+use crate::pause::PausePlugin;
+use crate::physics::PhysicsPlugin;
 use crate::pill::PillPlugin;
+use crate::pointer::PointerPlugin;
+use crate::replay::ReplayPlugin;
+use crate::rng::RngPlugin;
+use crate::score::ScorePlugin;
+use crate::tuning::TuningPlugin;
 use crate::ui::UiPlugin;
 
+pub use crate::arena::ArenaSize;
+pub use crate::bot::AutoPlay;
+pub use crate::level::LevelName;
+pub use crate::patient::{Ailment, Health, Patient, PatientHealedEvent};
+pub use crate::pill::{Pill, PillKind};
+pub use crate::pointer::{PointerEvent, PointerId, PointerPhase};
+pub use crate::replay::ReplayMode;
+pub use crate::rng::RngSeed;
+
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
-use bevy::window::Window;
+#[cfg(debug_assertions)]
 use bevy_rapier2d::prelude::*;
 
 use bevy::app::App;
@@ -33,23 +73,50 @@ enum GameState {
     Playing,
     // Here the menu is drawn and waiting for player interaction
     Menu,
+    // The run is over and its results are shown
+    GameOver,
 }
 
-pub struct GamePlugin;
+#[derive(Default)]
+pub struct GamePlugin {
+    /// Leave out everything that needs a window, renderer or sound card, so the game runs under `MinimalPlugins`
+    pub headless: bool,
+}
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        // brings the asset plugin, which the other plugins need
+        if self.headless {
+            app.add_plugin(HeadlessPlugin);
+        }
+
         app.add_state::<GameState>()
-            .add_plugin(LoadingPlugin)
-            .add_plugin(MenuPlugin)
-            .add_plugin(InternalAudioPlugin)
+            .add_plugin(CleanupPlugin::<GameState>::default())
             .add_plugin(PatientPlugin)
             .add_plugin(PillPlugin)
             .add_plugin(BeakerPlugin)
+            .add_plugin(DirectorPlugin)
+            .add_plugin(ScorePlugin)
+            .add_plugin(RngPlugin)
+            .add_plugin(ReplayPlugin)
+            .add_plugin(BotPlugin)
+            .add_plugin(ArenaPlugin)
+            .add_plugin(PhysicsPlugin)
+            .add_plugin(TuningPlugin)
+            .add_plugin(LevelPlugin)
+            .add_plugin(PointerPlugin);
+
+        if self.headless {
+            return;
+        }
+
+        app.add_plugin(LoadingPlugin)
+            .add_plugin(MenuPlugin)
+            .add_plugin(InternalAudioPlugin)
             .add_plugin(UiPlugin)
-            // .add_plugins(DefaultPlugins)
-            .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
-            .add_startup_system(setup_physics);
+            .add_plugin(GameOverPlugin)
+            .add_plugin(PausePlugin)
+            .add_plugin(CameraPlugin);
         // .add_system(print_ball_altitude);
 
         #[cfg(debug_assertions)]
@@ -62,33 +129,3 @@ impl Plugin for GamePlugin {
         }
     }
 }
-
-fn setup_physics(mut commands: Commands, window_q: Query<&Window, With<PrimaryWindow>>) {
-    let window = window_q.single();
-    // floor
-    commands
-        .spawn(Collider::cuboid(500.0, 10.0))
-        .insert(TransformBundle::from(Transform::from_xyz(
-            0.0,
-            -(window.resolution.height() / 2.),
-            0.0,
-        )));
-
-    // left wall
-    commands
-        .spawn(Collider::cuboid(10.0, 500.0))
-        .insert(TransformBundle::from(Transform::from_xyz(
-            -(window.resolution.width() / 2.),
-            0.,
-            0.0,
-        )));
-
-    // right wall
-    commands
-        .spawn(Collider::cuboid(10.0, 500.0))
-        .insert(TransformBundle::from(Transform::from_xyz(
-            window.resolution.width() / 2.,
-            0.,
-            0.0,
-        )));
-}
@@ -1,8 +1,14 @@
+use crate::level::{Level, LevelName};
+use crate::tuning::Tuning;
 use crate::GameState;
+use bevy::app::AppExit;
+use bevy::asset::{HandleUntyped, LoadState};
 use bevy::prelude::*;
 use bevy::utils::HashMap;
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::AudioSource;
+use serde::de::DeserializeOwned;
+use std::path::Path;
 
 pub struct LoadingPlugin;
 
@@ -16,10 +22,23 @@ impl Plugin for LoadingPlugin {
         )
         .add_collection_to_loading_state::<_, FontAssets>(GameState::Loading)
         .add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
-        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading);
+        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, TuningAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, LevelAssets>(GameState::Loading)
+        .add_startup_system(register_level_file)
+        .add_system(give_up_on_broken_level.in_set(OnUpdate(GameState::Loading)));
     }
 }
 
+/// Reads one of the game's RON files, whether the asset server or the headless simulation loaded it
+pub(crate) fn parse_ron_asset<T: DeserializeOwned>(
+    path: &Path,
+    bytes: &[u8],
+) -> Result<T, bevy::asset::Error> {
+    ron::de::from_bytes(bytes)
+        .map_err(|err| bevy::asset::Error::msg(format!("Can't parse {}: {}", path.display(), err)))
+}
+
 // the following asset collections will be loaded during the State `GameState::Loading`
 // when done loading, they will be inserted as resources (see <https://github.com/NiklasEi/bevy_asset_loader>)
 
@@ -55,3 +74,54 @@ pub struct TextureAssets {
     )]
     pub folder: HashMap<String, Handle<Image>>,
 }
+
+#[derive(AssetCollection, Resource)]
+pub struct TuningAssets {
+    // keeping the handle around keeps the file watched for changes
+    #[asset(path = "game.tuning.ron")]
+    pub tuning: Handle<Tuning>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct LevelAssets {
+    // the file depends on the level name, see `register_level_file`
+    #[asset(key = "level")]
+    pub level: Handle<Level>,
+}
+
+/// The file of the level picked by [`LevelName`]
+#[derive(Debug)]
+struct LevelFile(String);
+
+impl DynamicAsset for LevelFile {
+    fn load(&self, asset_server: &AssetServer) -> Vec<HandleUntyped> {
+        vec![asset_server.load_untyped(self.0.as_str())]
+    }
+
+    fn build(&self, world: &mut World) -> Result<DynamicAssetType, bevy::asset::Error> {
+        let asset_server = world.resource::<AssetServer>();
+        Ok(DynamicAssetType::Single(
+            asset_server.get_handle_untyped(self.0.as_str()),
+        ))
+    }
+}
+
+/// Loading would wait for a level that isn't there forever, e.g. after a typo in `--level`
+fn give_up_on_broken_level(
+    level_name: Res<LevelName>,
+    asset_server: Res<AssetServer>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    if asset_server.get_load_state(level_name.path().as_str()) == LoadState::Failed {
+        error!(
+            "Can't load level {}, see assets/{}",
+            level_name.0,
+            level_name.path()
+        );
+        ev_exit.send(AppExit);
+    }
+}
+
+fn register_level_file(level_name: Res<LevelName>, mut dynamic_assets: ResMut<DynamicAssets>) {
+    dynamic_assets.register_asset("level", Box::new(LevelFile(level_name.path())));
+}
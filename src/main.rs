@@ -5,27 +5,75 @@ use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy::winit::WinitWindows;
 use bevy::DefaultPlugins;
-use hospital_at_home::GamePlugin;
+use hospital_at_home::headless::Simulation;
+use hospital_at_home::{AutoPlay, GamePlugin, LevelName, ReplayMode, RngSeed};
 use std::io::Cursor;
 use winit::window::Icon;
 
 fn main() {
-    App::new()
-        .insert_resource(Msaa::Off)
+    let level = arg_value("--level").map_or_else(LevelName::default, LevelName);
+
+    // play a run with the bot, without a window, and print how it went
+    if let Some(seconds) = arg_value("--simulate") {
+        let seed =
+            arg_value("--seed").map_or(0, |seed| seed.parse().expect("--seed needs a number"));
+        let mut sim = Simulation::on_level(seed, level);
+        sim.autoplay();
+        sim.run_for(
+            seconds
+                .parse()
+                .expect("--simulate needs a number of seconds"),
+        );
+        println!("{}", sim.report());
+        return;
+    }
+
+    let mut app = App::new();
+    app.insert_resource(Msaa::Off)
         .insert_resource(ClearColor(Color::rgb(0.4, 0.4, 0.4)))
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Hospital at Home".to_string(), // ToDo
-                resolution: (800., 600.).into(),
-                canvas: Some("#bevy".to_owned()),
-                ..default()
-            }),
-            ..default()
-        }))
-        .add_system(bevy::window::close_on_esc)
-        .add_plugin(GamePlugin)
-        .add_system(set_window_icon.on_startup())
-        .run();
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        title: "Hospital at Home".to_string(), // ToDo
+                        resolution: (800., 600.).into(),
+                        canvas: Some("#bevy".to_owned()),
+                        ..default()
+                    }),
+                    ..default()
+                })
+                // so that changes to the tuning file show up right away
+                .set(AssetPlugin {
+                    watch_for_changes: !cfg!(target_arch = "wasm32"),
+                    ..default()
+                }),
+        )
+        .add_plugin(GamePlugin::default())
+        .insert_resource(level)
+        .add_system(set_window_icon.on_startup());
+
+    if let Some(seed) = arg_value("--seed") {
+        app.insert_resource(RngSeed(seed.parse().expect("--seed needs a number")));
+    }
+
+    if std::env::args().any(|arg| arg == "--autoplay") {
+        app.insert_resource(AutoPlay::default());
+    }
+
+    if let Some(path) = arg_value("--replay") {
+        app.insert_resource(ReplayMode::Play(path.into()));
+    } else if let Some(path) = arg_value("--record") {
+        app.insert_resource(ReplayMode::Record(path.into()));
+    }
+
+    app.run();
+}
+
+// Value following the given command line flag, e.g. `--seed 42`
+fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != flag);
+    args.next()?;
+    args.next()
 }
 
 // Sets the icon on windows and X11
@@ -1,6 +1,9 @@
+use crate::cleanup::DespawnOnExit;
 use crate::loading::FontAssets;
 use crate::loading::TextureAssets;
+use crate::pause::PauseState;
 use crate::GameState;
+use bevy::app::AppExit;
 use bevy::prelude::*;
 pub struct MenuPlugin;
 
@@ -10,15 +13,15 @@ impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ButtonColors>()
             .add_system(setup_menu.in_schedule(OnEnter(GameState::Menu)))
-            .add_system(click_play_button.in_set(OnUpdate(GameState::Menu)))
-            .add_system(cleanup_menu.in_schedule(OnExit(GameState::Menu)));
+            .add_system(start_on_touch.in_set(OnUpdate(GameState::Menu)))
+            .add_system(click_menu_buttons);
     }
 }
 
 #[derive(Resource)]
-struct ButtonColors {
-    normal: Color,
-    hovered: Color,
+pub(crate) struct ButtonColors {
+    pub(crate) normal: Color,
+    pub(crate) hovered: Color,
 }
 
 impl Default for ButtonColors {
@@ -30,8 +33,53 @@ impl Default for ButtonColors {
     }
 }
 
-#[derive(Component)]
-struct MenuEntity;
+/// What a menu button does when clicked
+#[derive(Component, Clone, Copy)]
+pub(crate) enum ButtonAction {
+    /// Start a new run, also from within a run
+    Play,
+    Resume,
+    MainMenu,
+    Quit,
+}
+
+/// Text on menu screens
+pub(crate) fn menu_text_style(font_assets: &FontAssets) -> TextStyle {
+    TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size: 30.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    }
+}
+
+/// Adds a button to a menu screen, which [`click_menu_buttons`] handles
+pub(crate) fn spawn_menu_button(
+    parent: &mut ChildBuilder,
+    label: &str,
+    action: ButtonAction,
+    font_assets: &FontAssets,
+    button_colors: &ButtonColors,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(200.0), Val::Px(50.0)),
+                margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            background_color: button_colors.normal.into(),
+            ..Default::default()
+        })
+        .insert(action)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                menu_text_style(font_assets),
+            ));
+        });
+}
 
 fn setup_menu(
     mut commands: Commands,
@@ -39,15 +87,10 @@ fn setup_menu(
     button_colors: Res<ButtonColors>,
     textures: Res<TextureAssets>,
 ) {
-    commands.spawn(Camera2dBundle::default());
-    commands.spawn(MenuEntity)
+    commands.spawn(DespawnOnExit(GameState::Menu))
     .insert(TextBundle::from_section(
         "Can you run a hospital?\nWatch out, every action you take\nmight have a... side effect.",
-        TextStyle {
-            font: font_assets.fira_sans.clone(),
-            font_size: 30.0,
-            color: Color::rgb(0.9, 0.9, 0.9),
-        })
+        menu_text_style(&font_assets))
         .with_text_alignment(TextAlignment::Right)
         .with_style(Style {
             position_type: PositionType::Absolute,
@@ -59,56 +102,68 @@ fn setup_menu(
             ..default()
         })
     );
-    commands.spawn(MenuEntity).insert(ImageBundle {
-        image: UiImage {
-            texture: textures
-                .folder
-                .get("textures/hospital.png")
-                .unwrap()
-                .clone(),
+    commands
+        .spawn(DespawnOnExit(GameState::Menu))
+        .insert(ImageBundle {
+            image: UiImage {
+                texture: textures
+                    .folder
+                    .get("textures/hospital.png")
+                    .unwrap()
+                    .clone(),
+                ..default()
+            },
             ..default()
-        },
-        ..default()
-    });
+        });
     commands
-        .spawn(MenuEntity)
-        .insert(ButtonBundle {
+        .spawn(DespawnOnExit(GameState::Menu))
+        .insert(NodeBundle {
             style: Style {
-                size: Size::new(Val::Px(120.0), Val::Px(50.0)),
-                margin: UiRect::all(Val::Auto),
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
-                ..Default::default()
+                ..default()
             },
-            background_color: button_colors.normal.into(),
-            ..Default::default()
+            ..default()
         })
         .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
+            spawn_menu_button(
+                parent,
                 "Play",
-                TextStyle {
-                    font: font_assets.fira_sans.clone(),
-                    font_size: 40.0,
-                    color: Color::rgb(0.9, 0.9, 0.9),
-                },
-            ));
+                ButtonAction::Play,
+                &font_assets,
+                &button_colors,
+            );
         });
 }
 
-fn click_play_button(
+fn start_on_touch(mut state: ResMut<NextState<GameState>>, mut touch_evr: EventReader<TouchInput>) {
+    for _ in touch_evr.iter() {
+        state.set(GameState::Playing);
+    }
+}
+
+/// Handles the buttons of every menu screen
+fn click_menu_buttons(
     button_colors: Res<ButtonColors>,
     mut state: ResMut<NextState<GameState>>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+    mut ev_exit: EventWriter<AppExit>,
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<Button>),
+        (&Interaction, &ButtonAction, &mut BackgroundColor),
+        Changed<Interaction>,
     >,
-    mut touch_evr: EventReader<TouchInput>,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    for (interaction, action, mut color) in &mut interaction_query {
         match *interaction {
-            Interaction::Clicked => {
-                state.set(GameState::Playing);
-            }
+            Interaction::Clicked => match action {
+                // entering `Playing` again runs the usual teardown and setup
+                ButtonAction::Play => state.set(GameState::Playing),
+                ButtonAction::Resume => pause_state.set(PauseState::Running),
+                ButtonAction::MainMenu => state.set(GameState::Menu),
+                ButtonAction::Quit => ev_exit.send(AppExit),
+            },
             Interaction::Hovered => {
                 *color = button_colors.hovered.into();
             }
@@ -117,14 +172,4 @@ fn click_play_button(
             }
         }
     }
-
-    for _ in touch_evr.iter() {
-        state.set(GameState::Playing);
-    }
-}
-
-fn cleanup_menu(mut commands: Commands, menu_entities: Query<Entity, With<MenuEntity>>) {
-    for e in menu_entities.iter() {
-        commands.entity(e).despawn_recursive();
-    }
 }
@@ -1,78 +1,251 @@
+use crate::cleanup::DespawnOnExit;
+use crate::director::Director;
+use crate::level::Level;
 use crate::loading::TextureAssets;
+use crate::physics::{FixedGameplaySet, Interpolated, PreStepVelocity};
 use crate::GameState;
 
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
-use bevy::window::Window;
 use bevy_rapier2d::prelude::*;
-use std::time::Duration;
 
-use crate::pill::Pill;
+use crate::effect::SideEffect;
+use crate::pill::{Pill, PillKind, PillWastedEvent};
+use crate::rng::GameRng;
+use crate::tuning::{PatientTuning, Tuning, WaveTuning};
+use crate::wave::Wave;
 use bevy_rapier2d::geometry::ActiveEvents;
 use bevy_rapier2d::pipeline::CollisionEvent;
 use rand::distributions::{Distribution, Standard};
 use rand::RngCore;
-use rand::{rngs::StdRng, SeedableRng};
 
 pub struct PatientPlugin;
-pub struct PatientHealedEvent;
 
+#[derive(Clone, Debug)]
+pub struct PatientHealedEvent {
+    /// The healed patient. It is despawned at the end of the frame the event is sent in.
+    pub patient: Entity,
+    pub ailment: Ailment,
+    pub pill: PillKind,
+    /// Where the patient was healed, in world coordinates
+    pub position: Vec2,
+    /// Velocity of the pill relative to the patient when they hit
+    pub relative_velocity: Vec2,
+    /// Seconds between the pill popping out of its beaker and healing the patient
+    pub time_since_fired: f32,
+    /// The pill bounced off something before it reached the patient
+    pub bank_shot: bool,
+    /// How many patients the pill healed, including this one
+    pub pill_heals: u32,
+}
+pub struct PatientDiedEvent;
+
+#[derive(Component)]
+pub struct Patient {
+    pub ailment: Ailment,
+    /// How many times the ailment got worse from a wrong pill
+    pub severity: u32,
+}
+
+impl Patient {
+    /// Everything a patient needs to be thrown around and healed, apart from their looks and shape
+    pub(crate) fn body(ailment: Ailment, tuning: &PatientTuning) -> impl Bundle {
+        (
+            Patient {
+                ailment,
+                severity: 0,
+            },
+            Health(MAX_HEALTH),
+            DespawnOnExit(GameState::Playing),
+            RigidBody::Dynamic,
+            Interpolated::default(),
+            Velocity::zero(),
+            PreStepVelocity::default(),
+            GravityScale(1.0),
+            ColliderMassProperties::Density(tuning.density),
+            Restitution::coefficient(tuning.restitution),
+            ActiveEvents::COLLISION_EVENTS,
+            ExternalImpulse::default(),
+        )
+    }
+}
+
+/// What is wrong with a patient. Only one kind of pill cures each ailment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Ailment {
+    Fever,
+    Shrunken,
+    Swollen,
+    Bedridden,
+}
+
+impl Ailment {
+    pub const ALL: [Ailment; 4] = [
+        Ailment::Fever,
+        Ailment::Shrunken,
+        Ailment::Swollen,
+        Ailment::Bedridden,
+    ];
+
+    /// Every ailment has its own patient texture, so players can tell them apart
+    pub fn texture(self) -> &'static str {
+        match self {
+            Ailment::Fever => "textures/patient_0.png",
+            Ailment::Shrunken => "textures/patient_1.png",
+            Ailment::Swollen => "textures/patient_2.png",
+            Ailment::Bedridden => "textures/patient_3.png",
+        }
+    }
+
+    /// The pill that cures this ailment
+    pub fn cure(self) -> PillKind {
+        match self {
+            Ailment::Fever => PillKind::Heal,
+            Ailment::Shrunken => PillKind::Grow,
+            Ailment::Swollen => PillKind::Shrink,
+            Ailment::Bedridden => PillKind::Float,
+        }
+    }
+}
+
+/// How long a patient lasts without the right pill
 #[derive(Component)]
-pub struct Patient;
+pub struct Health(pub f32);
+
+const MAX_HEALTH: f32 = 100.;
+/// Health lost per second, plus more for every time the ailment got worse
+const HEALTH_DRAIN: f32 = 4.;
+const HEALTH_DRAIN_PER_SEVERITY: f32 = 1.;
+
+/// A patient that ran out of health and is fading away
+#[derive(Component)]
+pub(crate) struct Dying {
+    timer: Timer,
+}
+
+/// How many patients were lost during the current run
+#[derive(Resource, Default)]
+pub struct LostPatients(pub u32);
+
+/// The run is over once this many patients are lost
+pub const MAX_LOST_PATIENTS: u32 = 10;
+
+/// Patients turn redder every time their ailment gets worse
+fn severity_color(severity: u32) -> Color {
+    let fade = 0.8_f32.powi(severity as i32);
+    Color::rgb(1., fade, fade)
+}
 
 /// This plugin handles patient related stuff like movement
 /// Patient logic is only active during the State `GameState::Playing`
 impl Plugin for PatientPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(setup_patient_spawning.in_schedule(OnEnter(GameState::Playing)))
-            .add_system(spawn_patient.in_set(OnUpdate(GameState::Playing)))
-            .add_system(handle_collisions.in_set(OnUpdate(GameState::Playing)))
+            .add_system(
+                spawn_patient
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .in_set(FixedGameplaySet),
+            )
+            // patients healed in a step are gone before their health runs out in it
+            .add_systems(
+                (handle_collisions, apply_system_buffers, drain_health)
+                    .chain()
+                    .in_set(FixedGameplaySet)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
             .add_system(move_patient.in_set(OnUpdate(GameState::Playing)))
-            .add_event::<PatientHealedEvent>();
+            .add_system(fade_dying_patients.in_set(OnUpdate(GameState::Playing)))
+            .add_system(check_lost_patients.in_set(OnUpdate(GameState::Playing)))
+            .add_event::<PatientHealedEvent>()
+            .add_event::<PatientDiedEvent>();
     }
 }
 
 #[derive(Resource)]
-struct PatientSpawnConfig {
+pub struct PatientSpawnConfig {
     /// How often to spawn a new patient? (repeating timer)
     timer: Timer,
+    /// Number of the current wave, starting at 1
+    pub wave_number: u32,
+    wave: Wave,
+    /// How many patients of the current wave were spawned so far
+    spawned: u32,
+    /// Counts down the break once the current wave is fully spawned
+    rest: Timer,
+}
+
+impl PatientSpawnConfig {
+    fn start_wave(number: u32, tuning: &WaveTuning) -> Self {
+        let wave = Wave::new(number, tuning);
+        PatientSpawnConfig {
+            // create the repeating timer
+            timer: Timer::new(wave.interval, TimerMode::Repeating),
+            wave_number: number,
+            rest: Timer::new(wave.rest, TimerMode::Once),
+            wave,
+            spawned: 0,
+        }
+    }
+
+    /// Stop spawning patients for the rest of the wave
+    pub fn hold(&mut self) {
+        self.timer.pause();
+    }
+
+    /// Spawn faster (above 1) or slower (below 1) than the wave asks for
+    pub fn set_intensity(&mut self, intensity: f32) {
+        let interval = self.wave.interval.div_f32(intensity);
+        if self.timer.duration() != interval {
+            self.timer.set_duration(interval);
+        }
+    }
 }
 
 /// Configure our patient spawning algorithm
-fn setup_patient_spawning(mut commands: Commands) {
-    commands.insert_resource(PatientSpawnConfig {
-        // create the repeating timer
-        timer: Timer::new(Duration::from_secs(1), TimerMode::Repeating),
-    })
+fn setup_patient_spawning(mut commands: Commands, tuning: Res<Tuning>) {
+    commands.insert_resource(PatientSpawnConfig::start_wave(1, &tuning.waves));
+    commands.insert_resource(LostPatients::default());
 }
 
-const PATIENT_SCALE: f32 = 0.5;
+#[allow(clippy::too_many_arguments)]
 fn spawn_patient(
     mut commands: Commands,
     textures: Res<TextureAssets>,
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
     mut config: ResMut<PatientSpawnConfig>,
+    director: Res<Director>,
+    mut game_rng: ResMut<GameRng>,
     assets: Res<Assets<Image>>,
-    primary_window: Query<&Window, With<PrimaryWindow>>,
+    level: Res<Level>,
+    tuning: Res<Tuning>,
 ) {
+    // take a break once the whole wave is out, then start the next one
+    if config.spawned >= config.wave.patients {
+        config.rest.tick(fixed_time.period);
+        if config.rest.finished() {
+            *config = PatientSpawnConfig::start_wave(config.wave_number + 1, &tuning.waves);
+        }
+        return;
+    }
+
     // tick the timer
-    config.timer.tick(time.delta());
+    config.timer.tick(fixed_time.period);
 
     if !config.timer.finished() {
         return;
     }
+    config.spawned += 1;
 
-    let window = primary_window.single();
-    let mut rng = StdRng::from_entropy();
-    let text_path = format!("textures/patient_{}.png", rng.next_u32() % 4);
-    let text = textures.folder.get(&text_path).unwrap();
+    let rng = &mut game_rng.patients;
+    let ailments = config.wave.ailments;
+    let ailment = ailments[rng.next_u32() as usize % ailments.len()];
+    let text = textures.folder.get(ailment.texture()).unwrap();
     let img = assets.get(text).unwrap();
 
     let mut points = Vec::new();
 
     for _ in 0..10 {
-        let x: f32 = Standard.sample(&mut rng);
-        let y: f32 = Standard.sample(&mut rng);
+        let x: f32 = Standard.sample(rng);
+        let y: f32 = Standard.sample(rng);
         points.push(Vect::new(
             x * img.size().x - img.size().x / 2.,
             y * img.size().y - img.size().y / 2.,
@@ -103,70 +276,137 @@ fn spawn_patient(
         (52, 27),
     ];
 
-    let force_scale = 300.;
-    let torque_scale = 5.;
+    let force_scale = tuning.patient.force_scale * director.intensity;
+    let torque_scale = tuning.patient.torque_scale * director.intensity;
 
-    let x_force_sample: f32 = Standard.sample(&mut rng);
-    let y_force_sample: f32 = Standard.sample(&mut rng);
-    let torque_sample: f32 = Standard.sample(&mut rng);
+    let x_force_sample: f32 = Standard.sample(rng);
+    let y_force_sample: f32 = Standard.sample(rng);
+    let torque_sample: f32 = Standard.sample(rng);
 
     let x_force = x_force_sample * force_scale - force_scale / 2.;
     let y_force = y_force_sample * force_scale - force_scale / 2.;
     let torque_force = torque_sample * torque_scale - torque_scale / 2.;
 
-    let x_pos_sample: f32 = Standard.sample(&mut rng);
-    let y_pos_sample: f32 = Standard.sample(&mut rng);
+    let zones = level.spawn_zones();
+    // only pick a zone when there is a choice, so the random sequence stays the same otherwise
+    let zone = if zones.len() > 1 {
+        zones[rng.next_u32() as usize % zones.len()]
+    } else {
+        zones[0]
+    };
+    let x_pos_sample: f32 = Standard.sample(rng);
+    let y_pos_sample: f32 = Standard.sample(rng);
+    let position = zone.lerp(Vec2::new(x_pos_sample, y_pos_sample));
 
     commands
         .spawn(SpriteBundle {
             texture: text.clone(),
-            transform: Transform::from_translation(Vec3::new(
-                (x_pos_sample - 0.5) * window.width(),
-                (y_pos_sample - 0.5) * window.height(),
+            transform: Transform::from_translation(position.extend(1.)).with_scale(Vec3::new(
+                tuning.patient.scale,
+                tuning.patient.scale,
                 1.,
-            ))
-            .with_scale(Vec3::new(PATIENT_SCALE, PATIENT_SCALE, 1.)),
+            )),
             ..Default::default()
         })
-        .insert(Patient)
-        .insert(RigidBody::Dynamic)
+        .insert(Patient::body(ailment, &tuning.patient))
         .insert(Collider::convex_hull(&points).unwrap())
-        .insert(ColliderMassProperties::Density(2.0))
-        .insert(Restitution::coefficient(0.7))
-        .insert(ActiveEvents::COLLISION_EVENTS)
         .insert(ExternalImpulse {
             impulse: Vec2::new(x_force, y_force),
             torque_impulse: torque_force,
         });
 }
 
-fn handle_collisions(
+/// Everything a side effect may change on a patient
+type PatientBody<'a> = (
+    &'a mut Patient,
+    &'a mut Sprite,
+    &'a mut Transform,
+    &'a mut GravityScale,
+    &'a mut Restitution,
+    &'a mut ExternalImpulse,
+);
+/// Patients that pills fall through instead of bouncing off
+type Intangible = Or<(With<Dying>, With<Sensor>)>;
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_collisions(
     mut collision_events: EventReader<CollisionEvent>,
-    patients: Query<&Patient>,
-    pills: Query<&Pill>,
+    mut patients: Query<PatientBody>,
+    mut pills: Query<&mut Pill>,
+    velocities: Query<&PreStepVelocity>,
+    dying: Query<(), Intangible>,
     mut commands: Commands,
     mut ev_heal_pt: EventWriter<PatientHealedEvent>,
+    mut ev_wasted: EventWriter<PillWastedEvent>,
 ) {
+    // despawns only happen at the end of the frame, so remember which patients were already healed
+    let mut healed = Vec::new();
+
     for ev in collision_events.iter() {
-        match ev {
-            CollisionEvent::Started(e1, e2, _flags) => {
-                if let Ok(_pill) = pills.get(*e1) {
-                    if let Ok(_patient) = patients.get(*e2) {
-                        commands.entity(*e1).despawn_recursive();
-                        commands.entity(*e2).despawn_recursive();
-                        ev_heal_pt.send(PatientHealedEvent);
-                    }
-                }
-
-                if let Ok(_pill) = pills.get(*e2) {
-                    if let Ok(_patient) = patients.get(*e1) {
-                        commands.entity(*e1).despawn_recursive();
-                        commands.entity(*e2).despawn_recursive();
-                        ev_heal_pt.send(PatientHealedEvent);
-                    }
-                }
+        let CollisionEvent::Started(e1, e2, _flags) = ev else {
+            continue;
+        };
+
+        let (pill_entity, other_entity) = if pills.contains(*e1) {
+            (*e1, *e2)
+        } else if pills.contains(*e2) {
+            (*e2, *e1)
+        } else {
+            continue;
+        };
+        let mut pill = pills.get_mut(pill_entity).unwrap();
+
+        let Ok((mut patient, mut sprite, mut transform, mut gravity, mut restitution, mut impulse)) =
+            patients.get_mut(other_entity)
+        else {
+            // pills fall through patients that passed away, that isn't a bounce
+            if dying.contains(other_entity) {
+                continue;
+            }
+            // walls and other pills
+            pill.bounces += 1;
+            // when two pills hit, both of them bounced
+            if let Ok(mut other_pill) = pills.get_mut(other_entity) {
+                other_pill.bounces += 1;
+            }
+            continue;
+        };
+
+        if pill.doses == 0 || healed.contains(&other_entity) {
+            continue;
+        }
+        pill.doses -= 1;
+
+        if patient.ailment.cure() == pill.kind {
+            pill.heals += 1;
+            healed.push(other_entity);
+            commands.entity(other_entity).despawn_recursive();
+            // as they came into the step, since the bounce already changed their velocities
+            let velocity = |e| velocities.get(e).map_or(Vec2::ZERO, |v| v.0);
+            ev_heal_pt.send(PatientHealedEvent {
+                patient: other_entity,
+                ailment: patient.ailment,
+                pill: pill.kind,
+                position: transform.translation.truncate(),
+                relative_velocity: velocity(pill_entity) - velocity(other_entity),
+                time_since_fired: pill.age(),
+                bank_shot: pill.bounces > 0,
+                pill_heals: pill.heals,
+            });
+        } else {
+            let effect = pill.kind.def().effect;
+            if effect == SideEffect::Worsen {
+                patient.severity += 1;
+                sprite.color = severity_color(patient.severity);
+            }
+            effect.apply(&mut transform, &mut gravity, &mut restitution, &mut impulse);
+        }
+
+        if pill.doses == 0 {
+            commands.entity(pill_entity).despawn_recursive();
+            if pill.heals == 0 {
+                ev_wasted.send(PillWastedEvent);
             }
-            _ => {}
         }
     }
 }
@@ -178,3 +418,51 @@ fn move_patient(
     _ext_impulses: Query<&mut ExternalImpulse>,
 ) {
 }
+
+fn drain_health(
+    mut commands: Commands,
+    fixed_time: Res<FixedTime>,
+    mut patients: Query<(Entity, &Patient, &mut Health, &mut Sprite), Without<Dying>>,
+    mut lost: ResMut<LostPatients>,
+    mut ev_died: EventWriter<PatientDiedEvent>,
+) {
+    for (entity, patient, mut health, mut sprite) in patients.iter_mut() {
+        let drain = HEALTH_DRAIN + HEALTH_DRAIN_PER_SEVERITY * patient.severity as f32;
+        health.0 -= drain * fixed_time.period.as_secs_f32();
+        if health.0 > 0. {
+            continue;
+        }
+
+        // dead patients turn grey and drop through the floor while fading out
+        sprite.color = Color::GRAY;
+        commands
+            .entity(entity)
+            .remove::<Patient>()
+            .insert(Sensor)
+            .insert(Dying {
+                timer: Timer::from_seconds(1., TimerMode::Once),
+            });
+        lost.0 += 1;
+        ev_died.send(PatientDiedEvent);
+    }
+}
+
+fn fade_dying_patients(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut dying: Query<(Entity, &mut Dying, &mut Sprite)>,
+) {
+    for (entity, mut dying, mut sprite) in dying.iter_mut() {
+        dying.timer.tick(time.delta());
+        sprite.color.set_a(dying.timer.percent_left());
+        if dying.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn check_lost_patients(lost: Res<LostPatients>, mut state: ResMut<NextState<GameState>>) {
+    if lost.is_changed() && lost.0 >= MAX_LOST_PATIENTS {
+        state.set(GameState::GameOver);
+    }
+}
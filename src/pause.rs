@@ -0,0 +1,125 @@
+use crate::beaker::drop_aims;
+use crate::cleanup::{CleanupPlugin, DespawnOnExit};
+use crate::loading::FontAssets;
+use crate::menu::{menu_text_style, spawn_menu_button, ButtonAction, ButtonColors};
+use crate::physics::FixedGameplaySet;
+use crate::pointer::{forget_pointers, PointerSet};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+use bevy_rapier2d::prelude::*;
+
+pub struct PausePlugin;
+
+/// Whether the game is currently paused. Only meaningful during `GameState::Playing`.
+/// This is a separate state, so that pausing doesn't exit `GameState::Playing` and tear down the run.
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// This plugin pauses the game on Escape or when the window loses focus
+/// While paused, all `GameState::Playing` systems, pointer input and the physics simulation are stopped
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<PauseState>()
+            .add_plugin(CleanupPlugin::<PauseState>::default())
+            .configure_set(OnUpdate(GameState::Playing).run_if(in_state(PauseState::Running)))
+            // clicks on the pause menu shouldn't reach the beakers under it once the game resumes
+            .configure_set(PointerSet.run_if(in_state(PauseState::Running)))
+            .add_system(toggle_pause.run_if(in_state(GameState::Playing)))
+            .add_system(pause_on_focus_lost.run_if(in_state(GameState::Playing)))
+            .add_system(setup_pause_menu.in_schedule(OnEnter(PauseState::Paused)))
+            .add_system(freeze_physics.in_schedule(OnEnter(PauseState::Paused)))
+            // whatever was being aimed when the game paused is let go of without firing
+            .add_system(forget_pointers.in_schedule(OnEnter(PauseState::Paused)))
+            .add_system(drop_aims.in_schedule(OnEnter(PauseState::Paused)))
+            .add_system(unfreeze_physics.in_schedule(OnExit(PauseState::Paused)))
+            .add_system(unpause.in_schedule(OnExit(GameState::Playing)));
+
+        app.edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+            schedule.configure_set(FixedGameplaySet.run_if(in_state(PauseState::Running)));
+        });
+    }
+}
+
+fn toggle_pause(
+    keys: Res<Input<KeyCode>>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_pause_state.set(match pause_state.0 {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
+    }
+}
+
+fn pause_on_focus_lost(
+    mut focus_events: EventReader<WindowFocused>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if focus_events.iter().any(|ev| !ev.focused) {
+        next_pause_state.set(PauseState::Paused);
+    }
+}
+
+fn unpause(mut next_pause_state: ResMut<NextState<PauseState>>) {
+    next_pause_state.set(PauseState::Running);
+}
+
+fn freeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn unfreeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
+
+fn setup_pause_menu(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    commands
+        .spawn(DespawnOnExit(PauseState::Paused))
+        .insert(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "Paused",
+                    TextStyle {
+                        font_size: 40.0,
+                        ..menu_text_style(&font_assets)
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+
+            for (label, action) in [
+                ("Resume", ButtonAction::Resume),
+                ("Restart", ButtonAction::Play),
+                ("Quit", ButtonAction::Quit),
+            ] {
+                spawn_menu_button(parent, label, action, &font_assets, &button_colors);
+            }
+        });
+}
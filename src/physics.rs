@@ -0,0 +1,149 @@
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::time::{TimeSystem, TimeUpdateStrategy};
+use bevy::transform::TransformSystem;
+use bevy_rapier2d::prelude::*;
+use std::time::Duration;
+
+pub struct PhysicsPlugin;
+
+/// Length of one physics step, in seconds
+pub const FIXED_TIMESTEP: f32 = 1. / 60.;
+
+/// Gameplay that has to advance in lockstep with the physics, no matter the frame rate.
+/// These systems run in `CoreSchedule::FixedUpdate` right after every physics step, and only while playing.
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct FixedGameplaySet;
+
+/// While this resource exists, every frame advances the game by exactly one physics step,
+/// no matter how long the frame really took. Runs that have to play out the same on every machine need it.
+#[derive(Resource)]
+pub struct Lockstep;
+
+/// This plugin steps the physics simulation at a fixed rate, independent of the frame rate
+/// Rigid bodies with [`Interpolated`] are drawn in between their last two physics steps, so they still move smoothly
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(
+            RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0)
+                .with_default_system_setup(false),
+        )
+        .insert_resource(FixedTime::new_from_secs(FIXED_TIMESTEP))
+        .insert_resource(RapierConfiguration {
+            timestep_mode: TimestepMode::Fixed {
+                dt: FIXED_TIMESTEP,
+                substeps: 1,
+            },
+            ..default()
+        })
+        .add_system(
+            advance_lockstep_clock
+                .in_base_set(CoreSet::First)
+                .before(TimeSystem)
+                .run_if(resource_exists::<Lockstep>()),
+        )
+        .add_system(
+            interpolate_transforms
+                .in_base_set(CoreSet::PostUpdate)
+                .before(TransformSystem::TransformPropagate),
+        );
+
+        app.edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+            schedule
+                .configure_sets(
+                    (
+                        PhysicsSet::SyncBackend,
+                        PhysicsSet::SyncBackendFlush,
+                        PhysicsSet::StepSimulation,
+                        PhysicsSet::Writeback,
+                        FixedGameplaySet,
+                    )
+                        .chain(),
+                )
+                .configure_set(FixedGameplaySet.run_if(in_state(GameState::Playing)))
+                .add_systems(
+                    RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::SyncBackend)
+                        .in_base_set(PhysicsSet::SyncBackend),
+                )
+                .add_systems(
+                    RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::SyncBackendFlush)
+                        .in_base_set(PhysicsSet::SyncBackendFlush),
+                )
+                .add_systems(
+                    RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::StepSimulation)
+                        .in_base_set(PhysicsSet::StepSimulation),
+                )
+                .add_systems(
+                    RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::Writeback)
+                        .in_base_set(PhysicsSet::Writeback),
+                )
+                .add_system(restore_physics_transforms.before(PhysicsSet::SyncBackend))
+                .add_system(
+                    record_pre_step_velocities
+                        .after(PhysicsSet::SyncBackendFlush)
+                        .before(PhysicsSet::StepSimulation),
+                )
+                .add_system(
+                    record_physics_transforms
+                        .after(PhysicsSet::Writeback)
+                        .before(FixedGameplaySet),
+                );
+        });
+    }
+}
+
+/// Poses of a rigid body after its last two physics steps
+#[derive(Component, Default)]
+pub struct Interpolated {
+    previous: Option<(Vec3, Quat)>,
+    current: Option<(Vec3, Quat)>,
+}
+
+/// Velocity of a rigid body going into the last physics step, before any collision in it bounced the body off.
+/// `Velocity` itself already holds the velocity after the bounces once gameplay sees a collision.
+#[derive(Component, Default)]
+pub struct PreStepVelocity(pub Vec2);
+
+fn record_pre_step_velocities(mut bodies: Query<(&Velocity, &mut PreStepVelocity)>) {
+    for (velocity, mut pre_step) in bodies.iter_mut() {
+        pre_step.0 = velocity.linvel;
+    }
+}
+
+/// Undo the interpolation before stepping, so that rapier doesn't mistake it for the body being moved
+fn restore_physics_transforms(mut bodies: Query<(&Interpolated, &mut Transform)>) {
+    for (interpolated, mut transform) in bodies.iter_mut() {
+        if let Some((translation, rotation)) = interpolated.current {
+            transform.translation = translation;
+            transform.rotation = rotation;
+        }
+    }
+}
+
+fn record_physics_transforms(mut bodies: Query<(&mut Interpolated, &Transform)>) {
+    for (mut interpolated, transform) in bodies.iter_mut() {
+        let pose = (transform.translation, transform.rotation);
+        interpolated.previous = interpolated.current.or(Some(pose));
+        interpolated.current = Some(pose);
+    }
+}
+
+fn interpolate_transforms(
+    fixed_time: Res<FixedTime>,
+    mut bodies: Query<(&Interpolated, &mut Transform)>,
+) {
+    let alpha = fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32();
+    for (interpolated, mut transform) in bodies.iter_mut() {
+        if let (Some(previous), Some(current)) = (interpolated.previous, interpolated.current) {
+            transform.translation = previous.0.lerp(current.0, alpha);
+            transform.rotation = previous.1.slerp(current.1, alpha);
+        }
+    }
+}
+
+fn advance_lockstep_clock(time: Res<Time>, mut strategy: ResMut<TimeUpdateStrategy>) {
+    // `TimeUpdateStrategy::ManualDuration` counts from the real current time, so step the instant ourselves
+    let last_update = time.last_update().unwrap_or_else(|| time.startup());
+    *strategy =
+        TimeUpdateStrategy::ManualInstant(last_update + Duration::from_secs_f32(FIXED_TIMESTEP));
+}
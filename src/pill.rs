@@ -1,4 +1,10 @@
+use crate::beaker::TapSet;
+use crate::cleanup::DespawnOnExit;
+use crate::effect::SideEffect;
 use crate::loading::TextureAssets;
+use crate::physics::{FixedGameplaySet, Interpolated, PreStepVelocity};
+use crate::rng::GameRng;
+use crate::tuning::{PillTuning, Tuning};
 use crate::GameState;
 
 use bevy::prelude::*;
@@ -6,24 +12,171 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use rand::distributions::{Distribution, Standard};
 use rand::RngCore;
-use rand::{rngs::StdRng, SeedableRng};
+use serde::Deserialize;
 pub struct PillPlugin;
 use bevy_rapier2d::geometry::ActiveEvents;
 
 #[derive(Component)]
-pub struct Pill;
+pub struct Pill {
+    pub kind: PillKind,
+    /// How many more patients this pill can be swallowed by
+    pub doses: u32,
+    /// How many patients this pill healed so far
+    pub heals: u32,
+    /// How often this pill bounced off something that isn't a patient
+    pub bounces: u32,
+    /// Pills that lie around for too long are cleaned up
+    lifetime: Timer,
+}
+
+impl Pill {
+    pub fn new(kind: PillKind, doses: u32) -> Self {
+        Pill {
+            kind,
+            doses,
+            heals: 0,
+            bounces: 0,
+            lifetime: Timer::from_seconds(PILL_LIFETIME, TimerMode::Once),
+        }
+    }
+
+    /// Everything a pill needs to fly around and heal, apart from its looks and shape
+    pub(crate) fn body(kind: PillKind, doses: u32, tuning: &PillTuning) -> impl Bundle {
+        (
+            Pill::new(kind, doses),
+            DespawnOnExit(GameState::Playing),
+            RigidBody::Dynamic,
+            Interpolated::default(),
+            Velocity::zero(),
+            PreStepVelocity::default(),
+            ColliderMassProperties::Density(tuning.density),
+            Restitution::coefficient(tuning.restitution),
+            ActiveEvents::COLLISION_EVENTS,
+            ExternalImpulse::default(),
+        )
+    }
+
+    /// Seconds since the pill popped out of its beaker
+    pub fn age(&self) -> f32 {
+        self.lifetime.elapsed_secs()
+    }
+}
+
+/// A pill ran out of doses or time without healing anyone
+pub struct PillWastedEvent;
+
+/// Every now and then a beaker pops out a bigger pill that is good for two patients
+const DOUBLE_DOSE_CHANCE: f32 = 0.1;
+/// Seconds until an unused pill disappears
+const PILL_LIFETIME: f32 = 6.;
+
+/// The different kinds of pills the beakers pop out
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum PillKind {
+    Heal,
+    Grow,
+    Shrink,
+    Float,
+    Spin,
+    Bounce,
+}
+
+/// Everything that makes one kind of pill different from the others
+pub struct PillKindDef {
+    pub kind: PillKind,
+    pub texture: &'static str,
+    pub color: Color,
+    pub effect: SideEffect,
+}
+
+/// One entry per [`PillKind`], in the same order
+const PILL_KINDS: [PillKindDef; 6] = [
+    PillKindDef {
+        kind: PillKind::Heal,
+        texture: "textures/pill_0.png",
+        color: Color::WHITE,
+        effect: SideEffect::Worsen,
+    },
+    PillKindDef {
+        kind: PillKind::Grow,
+        texture: "textures/pill_1.png",
+        color: Color::WHITE,
+        effect: SideEffect::Scale(1.5),
+    },
+    PillKindDef {
+        kind: PillKind::Shrink,
+        texture: "textures/pill_2.png",
+        color: Color::WHITE,
+        effect: SideEffect::Scale(0.6),
+    },
+    PillKindDef {
+        kind: PillKind::Float,
+        texture: "textures/pill_3.png",
+        color: Color::WHITE,
+        effect: SideEffect::Gravity(0.25),
+    },
+    PillKindDef {
+        kind: PillKind::Spin,
+        texture: "textures/pill_0.png",
+        color: Color::rgb(1.0, 0.6, 0.6),
+        effect: SideEffect::Spin(10.),
+    },
+    PillKindDef {
+        kind: PillKind::Bounce,
+        texture: "textures/pill_1.png",
+        color: Color::rgb(0.6, 0.6, 1.0),
+        effect: SideEffect::Bounce(1.2),
+    },
+];
+
+impl PillKind {
+    pub const ALL: [PillKind; 6] = [
+        PillKind::Heal,
+        PillKind::Grow,
+        PillKind::Shrink,
+        PillKind::Float,
+        PillKind::Spin,
+        PillKind::Bounce,
+    ];
+
+    pub fn def(self) -> &'static PillKindDef {
+        &PILL_KINDS[self as usize]
+    }
+}
 
+#[derive(Clone)]
 pub struct SpawnPillEvent {
     pub pos: Vec3,
     pub dir: Quat,
+    /// The pill is one of these kinds, picked at random
+    pub kinds: Vec<PillKind>,
+    /// Aimed pills leave at exactly this velocity, others get the same push whatever their weight
+    pub velocity: Option<Vec2>,
 }
 
 impl Plugin for PillPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(move_pill.in_set(OnUpdate(GameState::Playing)))
             // .add_system(handle_mouse.in_set(OnUpdate(GameState::Playing)))
-            .add_system(spawn_pills.in_set(OnUpdate(GameState::Playing)))
-            .add_event::<SpawnPillEvent>();
+            .init_resource::<PillQueue>()
+            .add_system(clear_pill_queue.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(
+                queue_pills
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(TapSet::Fire),
+            )
+            .add_system(
+                spawn_pills
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .in_set(FixedGameplaySet),
+            )
+            .add_system(
+                expire_pills
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .in_set(FixedGameplaySet),
+            )
+            .add_event::<SpawnPillEvent>()
+            .add_event::<PillWastedEvent>();
     }
 }
 
@@ -62,25 +215,51 @@ impl Plugin for PillPlugin {
 //     }
 // }
 
-const PILL_SCALE: f32 = 0.1;
+/// Pills waiting for the next physics step, so that they show up at the same step whatever the frame rate.
+/// Frames without a physics step would miss the events, so they are kept here until then.
+#[derive(Resource, Default)]
+pub(crate) struct PillQueue(Vec<SpawnPillEvent>);
 
-pub fn spawn_pills(
+fn clear_pill_queue(mut queue: ResMut<PillQueue>) {
+    queue.0.clear();
+}
+
+pub(crate) fn queue_pills(
+    mut queue: ResMut<PillQueue>,
+    mut ev_spawn_pill: EventReader<SpawnPillEvent>,
+) {
+    queue.0.extend(ev_spawn_pill.iter().cloned());
+}
+
+fn spawn_pills(
     mut commands: Commands,
     textures: Res<TextureAssets>,
-    mut ev_spawn_pill: EventReader<SpawnPillEvent>,
+    mut queue: ResMut<PillQueue>,
+    mut game_rng: ResMut<GameRng>,
     assets: Res<Assets<Image>>,
+    tuning: Res<Tuning>,
+    rapier: Res<RapierContext>,
 ) {
-    let mut rng = StdRng::from_entropy();
-    let text_path = format!("textures/pill_{}.png", rng.next_u32() % 4);
-    let text = textures.folder.get(&text_path).unwrap();
-    let img = assets.get(text).unwrap();
+    let tuning = &tuning.pill;
+    let GameRng { pills, effects, .. } = &mut *game_rng;
+
+    for ev in queue.0.drain(..) {
+        let kind = ev.kinds[effects.next_u32() as usize % ev.kinds.len()];
+        let text = textures.folder.get(kind.def().texture).unwrap();
+        let img = assets.get(text).unwrap();
+
+        let double_dose_sample: f32 = Standard.sample(pills);
+        let (doses, scale) = if double_dose_sample < DOUBLE_DOSE_CHANCE {
+            (2, tuning.scale * 1.4)
+        } else {
+            (1, tuning.scale)
+        };
 
-    for ev in ev_spawn_pill.iter() {
         let mut points = Vec::new();
 
         for _ in 0..10 {
-            let x: f32 = Standard.sample(&mut rng);
-            let y: f32 = Standard.sample(&mut rng);
+            let x: f32 = Standard.sample(pills);
+            let y: f32 = Standard.sample(pills);
             points.push(Vect::new(
                 x * img.size().x - img.size().x / 2.,
                 y * img.size().y - img.size().y / 2.,
@@ -88,40 +267,49 @@ pub fn spawn_pills(
         }
 
         // random torque
-        let torque_scale = 0.05;
-        let torque_sample: f32 = Standard.sample(&mut rng);
+        let torque_scale = tuning.torque_scale;
+        let torque_sample: f32 = Standard.sample(pills);
         let torque_impulse = torque_sample * torque_scale - torque_scale / 2.;
 
-        let force_scale = 300.;
+        let force_scale = tuning.force_scale;
         // random forces
         /*
 
-        let x_force_sample: f32 = Standard.sample(&mut rng);
-        let y_force_sample: f32 = Standard.sample(&mut rng);
+        let x_force_sample: f32 = Standard.sample(pills);
+        let y_force_sample: f32 = Standard.sample(pills);
 
         let x_force = x_force_sample * force_scale - force_scale / 2.;
         let y_force = y_force_sample * force_scale - force_scale / 2.;
         */
 
-        // force based on beaker rotation
-        let impulse: Vec2 = ev.dir.mul_vec3(Vec3::new(0., 1., 0.)).truncate() * force_scale;
+        let collider = Collider::convex_hull(&points).unwrap();
+        let impulse = match ev.velocity {
+            // the collider is scaled along with the sprite, and rapier measures mass in meters
+            Some(velocity) => {
+                let mass = collider.raw.mass_properties(tuning.density).mass() * scale * scale
+                    / rapier.physics_scale().powi(2);
+                velocity * mass
+            }
+            // force based on beaker rotation
+            None => ev.dir.mul_vec3(Vec3::new(0., 1., 0.)).truncate() * force_scale,
+        };
 
         // println!("ev.dir: {:?}, impulse: {:?}", ev.dir, impulse);
 
         commands
             .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: kind.def().color,
+                    ..default()
+                },
                 texture: text.clone(),
                 transform: Transform::from_translation(Vec3::new(ev.pos.x, ev.pos.y, 1.))
-                    .with_scale(Vec3::new(PILL_SCALE, PILL_SCALE, 1.)),
+                    .with_scale(Vec3::new(scale, scale, 1.)),
                 ..Default::default()
             })
-            .insert(Pill)
-            .insert(RigidBody::Dynamic)
+            .insert(Pill::body(kind, doses, tuning))
             // .insert(Collider::ball(60.0))
-            .insert(Collider::convex_hull(&points).unwrap())
-            .insert(ColliderMassProperties::Density(50.))
-            .insert(Restitution::coefficient(0.9))
-            .insert(ActiveEvents::COLLISION_EVENTS)
+            .insert(collider)
             .insert(ExternalImpulse {
                 impulse,
                 torque_impulse,
@@ -140,3 +328,32 @@ fn move_pill(
     //     ext_force.torque = 0.0;
     // }
 }
+
+fn expire_pills(
+    mut commands: Commands,
+    fixed_time: Res<FixedTime>,
+    mut pills: Query<(Entity, &mut Pill)>,
+    mut ev_wasted: EventWriter<PillWastedEvent>,
+) {
+    for (entity, mut pill) in pills.iter_mut() {
+        pill.lifetime.tick(fixed_time.period);
+        if pill.lifetime.just_finished() {
+            commands.entity(entity).despawn_recursive();
+            if pill.heals == 0 {
+                ev_wasted.send(PillWastedEvent);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_kind_has_its_own_def() {
+        for kind in PillKind::ALL {
+            assert_eq!(kind.def().kind, kind);
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use crate::arena::ArenaSize;
+use crate::camera::window_to_world;
+use crate::GameState;
+use bevy::ecs::system::SystemParam;
+use bevy::input::mouse::MouseButtonInput;
+use bevy::input::touch::{TouchInput, TouchPhase};
+use bevy::input::{ButtonState, InputSystem};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy::window::{PrimaryWindow, WindowResolution};
+
+pub struct PointerPlugin;
+
+/// This plugin turns the mouse and touches into [`PointerEvent`]s in world coordinates
+/// Pens show up as the mouse or as touches, depending on the platform, so they are covered as well
+/// Every finger is tracked on its own, so several beakers can be used at once
+impl Plugin for PointerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PointerEvent>()
+            .init_resource::<ActivePointers>()
+            .add_system(forget_pointers.in_schedule(OnExit(GameState::Playing)))
+            .add_systems(
+                (
+                    read_mouse.run_if(any_with_component::<PrimaryWindow>()),
+                    read_touches.run_if(any_with_component::<PrimaryWindow>()),
+                )
+                    .in_base_set(CoreSet::PreUpdate)
+                    .in_set(PointerSet)
+                    .after(InputSystem),
+            );
+    }
+}
+
+/// Systems that turn input into [`PointerEvent`]s
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub(crate) struct PointerSet;
+
+/// Something that points at the game
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PointerId {
+    Mouse,
+    Touch(u64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointerPhase {
+    /// The mouse button went down, or a finger touched the screen
+    Press,
+    /// A pressed pointer moved
+    Drag,
+    /// The mouse button went up, or a finger left the screen
+    Release,
+    /// The system took the pointer away, e.g. for a gesture, or the mouse was let go outside the window.
+    /// Unlike a release, this shouldn't do anything.
+    Cancel,
+}
+
+/// A pointer pressed, dragged or released somewhere in the world
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointerEvent {
+    pub pointer: PointerId,
+    pub phase: PointerPhase,
+    pub position: Vec2,
+    /// Where the pointer was pressed
+    pub start: Vec2,
+    /// How fast the pointer moved lately, in world units per second
+    pub velocity: Vec2,
+}
+
+/// Seconds of movement that the velocity of a pointer is measured over
+const VELOCITY_WINDOW: f32 = 0.1;
+
+/// A pointer that is pressed right now
+#[derive(Clone, Debug)]
+pub struct Pointer {
+    pub start: Vec2,
+    pub position: Vec2,
+    /// Times and positions the pointer was recently seen at, oldest first
+    recent: Vec<(f32, Vec2)>,
+}
+
+impl Pointer {
+    /// How fast the pointer moved over about the last [`VELOCITY_WINDOW`] seconds
+    pub fn velocity(&self) -> Vec2 {
+        match (self.recent.first(), self.recent.last()) {
+            (Some(&(first_time, first)), Some(&(last_time, last))) if last_time > first_time => {
+                (last - first) / (last_time - first_time)
+            }
+            _ => Vec2::ZERO,
+        }
+    }
+
+    fn move_to(&mut self, position: Vec2, now: f32) {
+        self.position = position;
+        self.recent.push((now, position));
+        // keep the last sample from before the window too, so that slowly reported movement still counts
+        let in_window = self
+            .recent
+            .iter()
+            .position(|(time, _)| now - time <= VELOCITY_WINDOW)
+            .unwrap_or(0);
+        self.recent.drain(..in_window.saturating_sub(1));
+    }
+}
+
+/// Every pointer that is pressed right now
+#[derive(Resource, Default, Debug)]
+pub struct ActivePointers(HashMap<PointerId, Pointer>);
+
+impl ActivePointers {
+    pub fn get(&self, pointer: PointerId) -> Option<&Pointer> {
+        self.0.get(&pointer)
+    }
+
+    /// Keeps track of the pointer and says what happened to it in world coordinates.
+    /// Pointers that aren't pressed can't be dragged or released, e.g. fingers that were already
+    /// on the screen when the game started.
+    /// `now` is the time in seconds.
+    fn track(
+        &mut self,
+        pointer: PointerId,
+        phase: PointerPhase,
+        position: Vec2,
+        now: f32,
+    ) -> Option<PointerEvent> {
+        let active = match phase {
+            PointerPhase::Press => {
+                let pressed = Pointer {
+                    start: position,
+                    position,
+                    recent: vec![(now, position)],
+                };
+                self.0.insert(pointer, pressed.clone());
+                pressed
+            }
+            PointerPhase::Drag => {
+                let active = self.0.get_mut(&pointer)?;
+                active.move_to(position, now);
+                active.clone()
+            }
+            PointerPhase::Release | PointerPhase::Cancel => {
+                let mut active = self.0.remove(&pointer)?;
+                active.move_to(position, now);
+                active
+            }
+        };
+        Some(PointerEvent {
+            pointer,
+            phase,
+            position,
+            start: active.start,
+            velocity: active.velocity(),
+        })
+    }
+}
+
+/// Turns spots in the window into spots in the world
+#[derive(SystemParam)]
+pub struct GameView<'w, 's> {
+    window: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    arena: Res<'w, ArenaSize>,
+}
+
+impl<'w, 's> GameView<'w, 's> {
+    /// Where in the world the mouse cursor is, if it is in the window
+    pub fn cursor(&self) -> Option<Vec2> {
+        let window = self.window.get_single().ok()?;
+        let cursor = window.cursor_position()?;
+        Some(window_to_world(cursor, &window.resolution, self.arena.0))
+    }
+
+    /// Where in the world a touch is
+    pub fn touch(&self, position: Vec2) -> Option<Vec2> {
+        let window = self.window.get_single().ok()?;
+        Some(touch_to_world(position, &window.resolution, self.arena.0))
+    }
+}
+
+/// Like [`window_to_world`], but touches count from the top left of the window
+pub fn touch_to_world(position: Vec2, window: &WindowResolution, arena: Vec2) -> Vec2 {
+    window_to_world(
+        Vec2::new(position.x, window.height() - position.y),
+        window,
+        arena,
+    )
+}
+
+/// Forgets every pressed pointer, so that moving or letting go of it later does nothing
+pub(crate) fn forget_pointers(mut pointers: ResMut<ActivePointers>) {
+    pointers.0.clear();
+}
+
+fn read_mouse(
+    time: Res<Time>,
+    mut buttons: EventReader<MouseButtonInput>,
+    mut moves: EventReader<CursorMoved>,
+    view: GameView,
+    mut pointers: ResMut<ActivePointers>,
+    mut pointer_events: EventWriter<PointerEvent>,
+) {
+    let now = time.elapsed_seconds();
+    let cursor = view.cursor();
+    if let (Some(position), Some(_)) = (cursor, moves.iter().last()) {
+        pointer_events.send_batch(pointers.track(
+            PointerId::Mouse,
+            PointerPhase::Drag,
+            position,
+            now,
+        ));
+    }
+
+    for button in buttons
+        .iter()
+        .filter(|button| button.button == MouseButton::Left)
+    {
+        let event = match (button.state, cursor) {
+            (ButtonState::Pressed, Some(position)) => {
+                pointers.track(PointerId::Mouse, PointerPhase::Press, position, now)
+            }
+            (ButtonState::Released, Some(position)) => {
+                pointers.track(PointerId::Mouse, PointerPhase::Release, position, now)
+            }
+            // let go outside the window
+            (ButtonState::Released, None) => {
+                let last_position = pointers.get(PointerId::Mouse).map(|mouse| mouse.position);
+                last_position.and_then(|position| {
+                    pointers.track(PointerId::Mouse, PointerPhase::Cancel, position, now)
+                })
+            }
+            (ButtonState::Pressed, None) => None,
+        };
+        pointer_events.send_batch(event);
+    }
+}
+
+fn read_touches(
+    time: Res<Time>,
+    mut touches: EventReader<TouchInput>,
+    view: GameView,
+    mut pointers: ResMut<ActivePointers>,
+    mut pointer_events: EventWriter<PointerEvent>,
+) {
+    let now = time.elapsed_seconds();
+    for touch in touches.iter() {
+        let phase = match touch.phase {
+            TouchPhase::Started => PointerPhase::Press,
+            TouchPhase::Moved => PointerPhase::Drag,
+            TouchPhase::Ended => PointerPhase::Release,
+            TouchPhase::Cancelled => PointerPhase::Cancel,
+        };
+        if let Some(position) = view.touch(touch.position) {
+            pointer_events.send_batch(pointers.track(
+                PointerId::Touch(touch.id),
+                phase,
+                position,
+                now,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARENA: Vec2 = Vec2::new(800., 600.);
+
+    /// A window of the given size in logical pixels
+    fn window(width: f32, height: f32, scale_factor: f64) -> WindowResolution {
+        let scale = scale_factor as f32;
+        let window = WindowResolution::new(width * scale, height * scale)
+            .with_scale_factor_override(scale_factor);
+        assert_eq!((window.width(), window.height()), (width, height));
+        window
+    }
+
+    #[test]
+    fn touches_count_from_the_top() {
+        for scale_factor in [1., 2.] {
+            let window = window(1200., 600., scale_factor);
+            assert_eq!(
+                touch_to_world(Vec2::new(200., 0.), &window, ARENA),
+                Vec2::new(-400., 300.)
+            );
+            assert_eq!(
+                touch_to_world(Vec2::new(1000., 600.), &window, ARENA),
+                Vec2::new(400., -300.)
+            );
+        }
+    }
+
+    #[test]
+    fn touches_are_tracked_one_by_one() {
+        let mut pointers = ActivePointers::default();
+        let left = PointerId::Touch(1);
+        let right = PointerId::Touch(2);
+        let (press, drag, release) = (
+            PointerPhase::Press,
+            PointerPhase::Drag,
+            PointerPhase::Release,
+        );
+
+        pointers.track(left, press, Vec2::new(-300., 0.), 0.);
+        pointers.track(right, press, Vec2::new(300., 0.), 0.);
+        pointers.track(left, drag, Vec2::new(-250., 50.), 0.);
+        let right_drag = pointers
+            .track(right, drag, Vec2::new(250., 50.), 0.)
+            .unwrap();
+        assert_eq!(right_drag.start, Vec2::new(300., 0.));
+
+        let left_release = pointers
+            .track(left, release, Vec2::new(-200., 100.), 0.)
+            .unwrap();
+        assert_eq!(left_release.start, Vec2::new(-300., 0.));
+        assert!(pointers.get(left).is_none());
+        assert_eq!(pointers.get(right).unwrap().position, Vec2::new(250., 50.));
+    }
+
+    #[test]
+    fn cancelled_touches_end_without_a_release() {
+        let mut pointers = ActivePointers::default();
+        let touch = PointerId::Touch(7);
+        pointers.track(touch, PointerPhase::Press, Vec2::ZERO, 0.);
+        let cancel = pointers
+            .track(touch, PointerPhase::Cancel, Vec2::ONE, 0.)
+            .unwrap();
+        assert_eq!(cancel.phase, PointerPhase::Cancel);
+        assert!(pointers.get(touch).is_none());
+        // a touch ID can be reused after its touch ended
+        assert!(pointers
+            .track(touch, PointerPhase::Drag, Vec2::ONE, 0.)
+            .is_none());
+        assert!(pointers
+            .track(touch, PointerPhase::Press, Vec2::ONE, 0.)
+            .is_some());
+    }
+
+    #[test]
+    fn pointers_that_never_pressed_are_ignored() {
+        let mut pointers = ActivePointers::default();
+        assert!(pointers
+            .track(PointerId::Touch(3), PointerPhase::Drag, Vec2::ZERO, 0.)
+            .is_none());
+        assert!(pointers
+            .track(PointerId::Mouse, PointerPhase::Release, Vec2::ZERO, 0.)
+            .is_none());
+    }
+
+    #[test]
+    fn velocity_follows_recent_movement() {
+        let mut pointers = ActivePointers::default();
+        let touch = PointerId::Touch(1);
+        pointers.track(touch, PointerPhase::Press, Vec2::ZERO, 0.);
+        // slow at first
+        pointers.track(touch, PointerPhase::Drag, Vec2::new(10., 0.), 0.4);
+        // then a quick flick up
+        pointers.track(touch, PointerPhase::Drag, Vec2::new(10., 10.), 0.44);
+        pointers.track(touch, PointerPhase::Drag, Vec2::new(10., 20.), 0.48);
+        let release = pointers
+            .track(touch, PointerPhase::Release, Vec2::new(10., 30.), 0.52)
+            .unwrap();
+        assert!(release.velocity.abs_diff_eq(Vec2::new(0., 250.), 1e-2));
+
+        // a pointer that stops before letting go isn't moving
+        pointers.track(touch, PointerPhase::Press, Vec2::ZERO, 1.);
+        pointers.track(touch, PointerPhase::Drag, Vec2::new(100., 0.), 1.05);
+        let release = pointers
+            .track(touch, PointerPhase::Release, Vec2::new(100., 0.), 1.5)
+            .unwrap();
+        assert_eq!(release.velocity, Vec2::ZERO);
+    }
+}
@@ -0,0 +1,311 @@
+use crate::beaker::{TapEvent, TapSet};
+use crate::physics::{FixedGameplaySet, Lockstep};
+use crate::rng::{GameRng, RngSeed};
+use crate::GameState;
+use bevy::prelude::*;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+pub struct ReplayPlugin;
+
+/// This plugin records the taps of a run to a file, or plays them back instead of the player's input
+/// Both only happen when a [`ReplayMode`] is inserted before the app starts
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunFrame>()
+            .add_startup_system(setup_replay)
+            .add_system(start_run.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(
+                count_run_frames
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .in_set(FixedGameplaySet),
+            )
+            .add_system(
+                record_taps
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(TapSet::Input)
+                    .before(TapSet::Fire)
+                    .run_if(resource_exists::<ReplayRecording>()),
+            )
+            .add_system(
+                save_recording
+                    .in_schedule(OnExit(GameState::Playing))
+                    .run_if(resource_exists::<ReplayRecording>()),
+            )
+            .add_system(
+                play_back_taps
+                    .in_set(OnUpdate(GameState::Playing))
+                    .in_set(TapSet::Input)
+                    .run_if(resource_exists::<ReplayPlayback>()),
+            );
+    }
+}
+
+/// Whether taps are recorded to, or played back from, a replay file.
+/// When recording, the first run goes to the given file and later ones, e.g. after Retry, get numbered files next to it.
+#[derive(Resource, Clone)]
+pub enum ReplayMode {
+    Record(PathBuf),
+    Play(PathBuf),
+}
+
+/// Physics steps since the current run started.
+/// Taps are recorded with the step they happened after, so a replay fires them between the same steps again.
+#[derive(Resource, Default)]
+pub struct RunFrame(pub u32);
+
+/// Everything needed to play a run again: its seed and the taps in it
+#[derive(Default)]
+pub struct Replay {
+    pub seed: u64,
+    pub taps: Vec<RecordedTap>,
+}
+
+pub struct RecordedTap {
+    /// [`RunFrame`] the tap happened after
+    pub frame: u32,
+    /// Where the tap happened, in world coordinates
+    pub pos: Vec2,
+    /// Velocity the tap aimed to launch a pill at
+    pub launch: Option<Vec2>,
+}
+
+impl Replay {
+    /// Replay files are plain text: a `seed <seed>` line, followed by one `<frame> <x> <y>` line per tap.
+    /// Aimed taps add the launch velocity: `<frame> <x> <y> <launch x> <launch y>`.
+    pub fn parse(text: &str) -> Option<Replay> {
+        let mut lines = text.lines();
+        let seed = lines.next()?.strip_prefix("seed ")?.trim().parse().ok()?;
+
+        let mut taps = Vec::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let mut parts = line.split_whitespace();
+            let frame = parts.next()?.parse().ok()?;
+            let x = parts.next()?.parse().ok()?;
+            let y = parts.next()?.parse().ok()?;
+            let launch = match (parts.next(), parts.next(), parts.next()) {
+                (None, None, None) => None,
+                (Some(x), Some(y), None) => Some(Vec2::new(x.parse().ok()?, y.parse().ok()?)),
+                _ => return None,
+            };
+            taps.push(RecordedTap {
+                frame,
+                pos: Vec2::new(x, y),
+                launch,
+            });
+        }
+
+        Some(Replay { seed, taps })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("seed {}\n", self.seed);
+        for tap in &self.taps {
+            // `{:?}` writes floats so that they read back exactly
+            write!(text, "{} {:?} {:?}", tap.frame, tap.pos.x, tap.pos.y).unwrap();
+            if let Some(launch) = tap.launch {
+                write!(text, " {:?} {:?}", launch.x, launch.y).unwrap();
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Taps of the current run, written to the file once the run is over
+#[derive(Resource, Default)]
+struct ReplayRecording {
+    replay: Replay,
+    /// Runs recorded so far, counting the current one
+    runs: u32,
+}
+
+/// The file the given run is recorded to: `replay.txt` for the first run, then `replay-2.txt` and so on
+fn numbered_path(path: &Path, run: u32) -> PathBuf {
+    if run <= 1 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, run, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, run),
+    };
+    path.with_file_name(name)
+}
+
+/// The replay being played back, and the next tap in it. Live input is ignored while it exists.
+#[derive(Resource)]
+pub(crate) struct ReplayPlayback {
+    replay: Replay,
+    next: usize,
+}
+
+fn setup_replay(mut commands: Commands, mode: Option<Res<ReplayMode>>) {
+    let Some(mode) = mode else {
+        return;
+    };
+
+    match &*mode {
+        ReplayMode::Record(path) => {
+            info!("Recording taps to {}", path.display());
+            commands.insert_resource(ReplayRecording::default());
+        }
+        ReplayMode::Play(path) => {
+            let replay = std::fs::read_to_string(path)
+                .ok()
+                .and_then(|text| Replay::parse(&text));
+            let Some(replay) = replay else {
+                error!(
+                    "Can't read replay {}, falling back to live input",
+                    path.display()
+                );
+                return;
+            };
+            info!(
+                "Playing back {} taps from {}",
+                replay.taps.len(),
+                path.display()
+            );
+            commands.insert_resource(RngSeed(replay.seed));
+            commands.insert_resource(ReplayPlayback { replay, next: 0 });
+            // a tap has to fire right after the step it was recorded after, so run exactly one step per frame
+            commands.insert_resource(Lockstep);
+        }
+    }
+}
+
+fn start_run(
+    mut frame: ResMut<RunFrame>,
+    recording: Option<ResMut<ReplayRecording>>,
+    playback: Option<ResMut<ReplayPlayback>>,
+) {
+    frame.0 = 0;
+    if let Some(mut recording) = recording {
+        recording.replay.taps.clear();
+        recording.runs += 1;
+    }
+    if let Some(mut playback) = playback {
+        playback.next = 0;
+    }
+}
+
+fn count_run_frames(mut frame: ResMut<RunFrame>) {
+    frame.0 += 1;
+}
+
+fn record_taps(
+    frame: Res<RunFrame>,
+    mut recording: ResMut<ReplayRecording>,
+    mut taps: EventReader<TapEvent>,
+) {
+    for tap in taps.iter() {
+        recording.replay.taps.push(RecordedTap {
+            frame: frame.0,
+            pos: tap.pos,
+            launch: tap.launch,
+        });
+    }
+}
+
+fn save_recording(
+    mode: Res<ReplayMode>,
+    mut recording: ResMut<ReplayRecording>,
+    game_rng: Res<GameRng>,
+) {
+    let ReplayMode::Record(path) = &*mode else {
+        return;
+    };
+    recording.replay.seed = game_rng.seed;
+    let path = numbered_path(path, recording.runs);
+    match std::fs::write(&path, recording.replay.to_text()) {
+        Ok(()) => info!("Saved replay to {}", path.display()),
+        Err(err) => error!("Can't save replay to {}: {}", path.display(), err),
+    }
+}
+
+fn play_back_taps(
+    frame: Res<RunFrame>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut ev_taps: EventWriter<TapEvent>,
+) {
+    while let Some(tap) = playback.replay.taps.get(playback.next) {
+        if tap.frame > frame.0 {
+            break;
+        }
+        ev_taps.send(TapEvent {
+            pos: tap.pos,
+            launch: tap.launch,
+            beaker: None,
+        });
+        playback.next += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_read_back_what_was_written() {
+        let replay = Replay {
+            seed: 1234,
+            taps: vec![
+                RecordedTap {
+                    frame: 1,
+                    pos: Vec2::new(-370., -270.),
+                    launch: None,
+                },
+                RecordedTap {
+                    frame: 60,
+                    pos: Vec2::new(370.5, 0.1),
+                    launch: Some(Vec2::new(-300.25, 123.456)),
+                },
+                RecordedTap {
+                    frame: 60,
+                    pos: Vec2::new(1. / 3., -2. / 3.),
+                    launch: None,
+                },
+                RecordedTap {
+                    frame: 301,
+                    pos: Vec2::ZERO,
+                    launch: Some(Vec2::new(0., 700.)),
+                },
+            ],
+        };
+
+        let read = Replay::parse(&replay.to_text()).unwrap();
+        assert_eq!(read.seed, replay.seed);
+        assert_eq!(read.taps.len(), replay.taps.len());
+        for (read, tap) in read.taps.iter().zip(&replay.taps) {
+            assert_eq!(
+                (read.frame, read.pos, read.launch),
+                (tap.frame, tap.pos, tap.launch)
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_replays_are_rejected() {
+        for text in [
+            "",
+            "12 0 0\n",
+            "seed twelve\n",
+            "seed 1\n12 0\n",
+            "seed 1\nnext 0 0\n",
+            "seed 1\n12 0 zero\n",
+            "seed 1\n12 0 0 300 up\n",
+            "seed 1\n12 0 0 300\n",
+            "seed 1\n12 0 0 300 0 0\n",
+        ] {
+            assert!(Replay::parse(text).is_none(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn later_runs_get_their_own_files() {
+        let path = Path::new("runs/replay.txt");
+        assert_eq!(numbered_path(path, 1), path);
+        assert_eq!(numbered_path(path, 3), Path::new("runs/replay-3.txt"));
+        assert_eq!(numbered_path(Path::new("replay"), 2), Path::new("replay-2"));
+    }
+}
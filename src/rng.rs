@@ -0,0 +1,50 @@
+use crate::GameState;
+use bevy::prelude::*;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+pub struct RngPlugin;
+
+/// This plugin reseeds all game randomness at the start of every run,
+/// so that the same seed and the same inputs play out the same way
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GameRng::new(0))
+            .add_system(seed_run.in_schedule(OnEnter(GameState::Playing)));
+    }
+}
+
+/// Seed every run should use. Without it, every run picks a fresh random seed.
+#[derive(Resource, Clone, Copy)]
+pub struct RngSeed(pub u64);
+
+/// All randomness of a run. Every part of the game draws from its own stream,
+/// so that e.g. firing an extra pill doesn't change the patients that spawn.
+#[derive(Resource)]
+pub struct GameRng {
+    /// The seed the current run was started with
+    pub seed: u64,
+    /// Ailments, shapes, positions and impulses of new patients
+    pub patients: StdRng,
+    /// Shapes, doses and spin of new pills
+    pub pills: StdRng,
+    /// Which kind of pill, and therefore which side effect, a beaker pops out
+    pub effects: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        let mut master = StdRng::seed_from_u64(seed);
+        GameRng {
+            seed,
+            patients: StdRng::seed_from_u64(master.next_u64()),
+            pills: StdRng::seed_from_u64(master.next_u64()),
+            effects: StdRng::seed_from_u64(master.next_u64()),
+        }
+    }
+}
+
+fn seed_run(mut commands: Commands, seed: Option<Res<RngSeed>>) {
+    let seed = seed.map_or_else(|| StdRng::from_entropy().next_u64(), |seed| seed.0);
+    info!("Run seed: {}", seed);
+    commands.insert_resource(GameRng::new(seed));
+}
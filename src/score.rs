@@ -0,0 +1,113 @@
+use crate::patient::{handle_collisions, PatientHealedEvent};
+use crate::physics::FixedGameplaySet;
+use crate::pill::PillWastedEvent;
+use crate::GameState;
+use bevy::prelude::*;
+
+pub struct ScorePlugin;
+
+/// This plugin turns heals and wasted pills into points
+/// Scoring is only active during the State `GameState::Playing`
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .init_resource::<ComboTimer>()
+            .add_system(reset_score.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(
+                score_heals
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .in_set(FixedGameplaySet)
+                    .after(handle_collisions),
+            )
+            .add_system(penalize_wasted_pills.in_set(OnUpdate(GameState::Playing)))
+            .add_system(
+                expire_combo
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .in_set(FixedGameplaySet)
+                    .before(score_heals),
+            );
+    }
+}
+
+const HEAL_POINTS: f32 = 100.;
+/// Heals less than this many seconds apart keep the combo going
+const COMBO_WINDOW: f32 = 2.;
+/// Every heal in a combo adds this much to the multiplier
+const COMBO_STEP: f32 = 0.5;
+const MAX_MULTIPLIER: f32 = 4.;
+/// Extra points for a pill that bounced off something before healing
+const BANK_SHOT_BONUS: u32 = 50;
+/// Extra points for every patient after the first that one pill heals
+const MULTI_HEAL_BONUS: u32 = 100;
+const WASTED_PILL_PENALTY: u32 = 10;
+
+#[derive(Resource, Default)]
+pub struct Score {
+    pub points: u32,
+    pub saved: u32,
+    /// Heals in quick succession, not counting the first one
+    pub combo: u32,
+}
+
+impl Score {
+    pub fn multiplier(&self) -> f32 {
+        (1. + self.combo as f32 * COMBO_STEP).min(MAX_MULTIPLIER)
+    }
+}
+
+/// Time left for the next heal to keep the combo going, if there was a heal lately.
+/// It only ticks along with the physics, so pausing doesn't break a combo.
+#[derive(Resource, Default)]
+struct ComboTimer(Option<Timer>);
+
+fn reset_score(mut score: ResMut<Score>, mut combo_timer: ResMut<ComboTimer>) {
+    *score = Score::default();
+    combo_timer.0 = None;
+}
+
+fn score_heals(
+    mut score: ResMut<Score>,
+    mut combo_timer: ResMut<ComboTimer>,
+    mut ev_healed: EventReader<PatientHealedEvent>,
+) {
+    for ev in ev_healed.iter() {
+        if combo_timer.0.is_some() {
+            score.combo += 1;
+        } else {
+            score.combo = 0;
+        }
+        combo_timer.0 = Some(Timer::from_seconds(COMBO_WINDOW, TimerMode::Once));
+
+        let mut points = (HEAL_POINTS * score.multiplier()) as u32;
+        if ev.bank_shot {
+            points += BANK_SHOT_BONUS;
+        }
+        points += MULTI_HEAL_BONUS * (ev.pill_heals - 1);
+
+        score.points += points;
+        score.saved += 1;
+    }
+}
+
+fn penalize_wasted_pills(mut score: ResMut<Score>, mut ev_wasted: EventReader<PillWastedEvent>) {
+    for _ev in ev_wasted.iter() {
+        score.points = score.points.saturating_sub(WASTED_PILL_PENALTY);
+    }
+}
+
+fn expire_combo(
+    fixed_time: Res<FixedTime>,
+    mut score: ResMut<Score>,
+    mut combo_timer: ResMut<ComboTimer>,
+) {
+    let Some(timer) = &mut combo_timer.0 else {
+        return;
+    };
+    if timer.tick(fixed_time.period).finished() {
+        combo_timer.0 = None;
+        // only write when the combo actually ends, so the score isn't marked as changed every step
+        if score.combo > 0 {
+            score.combo = 0;
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use crate::loading::{parse_ron_asset, TuningAssets};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+pub struct TuningPlugin;
+
+/// This plugin keeps the [`Tuning`] resource in sync with the tuning file
+/// Thanks to Bevy's filesystem watcher, saving the file changes the running game
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tuning>()
+            .add_asset::<Tuning>()
+            .add_asset_loader(TuningLoader)
+            .add_system(apply_tuning);
+    }
+}
+
+/// The tuning file, relative to the assets folder
+pub const TUNING_PATH: &str = "game.tuning.ron";
+
+/// Gameplay numbers that designers want to play with. Every value left out of the tuning file keeps its default.
+/// Changes only affect beakers, pills, patients and waves spawned after them.
+#[derive(Resource, TypeUuid, Deserialize, Clone, Debug, Default)]
+#[uuid = "5b2a1c57-2f8e-4f55-9a57-6f1a0b3c2d41"]
+#[serde(default)]
+pub struct Tuning {
+    pub beaker: BeakerTuning,
+    pub aim: AimTuning,
+    pub fling: FlingTuning,
+    pub pill: PillTuning,
+    pub patient: PatientTuning,
+    pub waves: WaveTuning,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BeakerTuning {
+    pub scale: f32,
+    /// Taps this far outside a beaker still fire it, since fingers aren't that precise
+    pub tap_margin: f32,
+    /// Draws where taps hit each beaker
+    pub show_hit_areas: bool,
+}
+
+impl Default for BeakerTuning {
+    fn default() -> Self {
+        BeakerTuning {
+            scale: 0.4,
+            tap_margin: 40.,
+            show_hit_areas: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AimTuning {
+    /// Degrees a beaker can be turned away from where the level points it, either way, by aiming or flinging
+    pub max_turn: f32,
+    /// Drags shorter than this are taps
+    pub min_drag: f32,
+    /// Dragging this far launches pills at `max_speed`
+    pub full_drag: f32,
+    /// Launch speeds of aimed and flung pills, in pixels per second
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// Dots in the preview of an aimed pill's path
+    pub preview_dots: u32,
+    /// Seconds of flight the preview shows
+    pub preview_seconds: f32,
+}
+
+impl Default for AimTuning {
+    fn default() -> Self {
+        AimTuning {
+            max_turn: 60.,
+            min_drag: 15.,
+            full_drag: 200.,
+            min_speed: 150.,
+            max_speed: 700.,
+            preview_dots: 20,
+            preview_seconds: 1.,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FlingTuning {
+    /// Swipes off a beaker at least this fast, in pixels per second, fling the pill instead of aiming
+    pub min_swipe_speed: f32,
+    /// Pills are flung this many times as fast as the swipe, up to the aim's `max_speed`
+    pub speed_factor: f32,
+}
+
+impl Default for FlingTuning {
+    fn default() -> Self {
+        FlingTuning {
+            min_swipe_speed: 1000.,
+            speed_factor: 0.5,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PillTuning {
+    pub scale: f32,
+    /// Impulse a tapped beaker fires pills with
+    pub force_scale: f32,
+    /// Pills spin with a random torque impulse of up to half this, either way
+    pub torque_scale: f32,
+    pub density: f32,
+    pub restitution: f32,
+}
+
+impl Default for PillTuning {
+    fn default() -> Self {
+        PillTuning {
+            scale: 0.1,
+            force_scale: 300.,
+            torque_scale: 0.05,
+            density: 50.,
+            restitution: 0.9,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PatientTuning {
+    pub scale: f32,
+    /// New patients are thrown with a random impulse of up to half this, in either direction
+    pub force_scale: f32,
+    /// New patients spin with a random torque impulse of up to half this, either way
+    pub torque_scale: f32,
+    pub density: f32,
+    pub restitution: f32,
+}
+
+impl Default for PatientTuning {
+    fn default() -> Self {
+        PatientTuning {
+            scale: 0.5,
+            force_scale: 300.,
+            torque_scale: 5.,
+            density: 2.,
+            restitution: 0.7,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct WaveTuning {
+    /// Seconds between two patients of the first wave
+    pub first_interval: f32,
+    /// Every wave after the first spawns this much faster
+    pub interval_factor: f32,
+    pub min_interval: f32,
+    /// Seconds between the end of one wave and the start of the next
+    pub rest: f32,
+}
+
+impl Default for WaveTuning {
+    fn default() -> Self {
+        WaveTuning {
+            first_interval: 1.5,
+            interval_factor: 0.85,
+            min_interval: 0.3,
+            rest: 4.,
+        }
+    }
+}
+
+impl Tuning {
+    /// Finds the first value the game can't work with, e.g. one it divides by or turns into a timer
+    pub(crate) fn check(&self) -> Result<(), String> {
+        let positive = [
+            ("beaker.scale", self.beaker.scale),
+            ("aim.full_drag", self.aim.full_drag),
+            ("aim.max_speed", self.aim.max_speed),
+            ("aim.preview_seconds", self.aim.preview_seconds),
+            ("pill.scale", self.pill.scale),
+            ("pill.density", self.pill.density),
+            ("patient.scale", self.patient.scale),
+            ("patient.density", self.patient.density),
+            ("waves.first_interval", self.waves.first_interval),
+            ("waves.interval_factor", self.waves.interval_factor),
+            ("waves.min_interval", self.waves.min_interval),
+        ];
+        let not_negative = [
+            ("aim.max_turn", self.aim.max_turn),
+            ("aim.min_speed", self.aim.min_speed),
+            ("waves.rest", self.waves.rest),
+        ];
+        for (name, value) in positive {
+            if value.is_nan() || value <= 0. {
+                return Err(format!("{} has to be above 0, not {}", name, value));
+            }
+        }
+        for (name, value) in not_negative {
+            if value.is_nan() || value < 0. {
+                return Err(format!("{} can't be below 0, not {}", name, value));
+            }
+        }
+        if self.aim.preview_dots == 0 {
+            return Err("aim.preview_dots has to be at least 1".to_string());
+        }
+        if self.aim.min_speed > self.aim.max_speed {
+            return Err(format!(
+                "aim.min_speed ({}) can't be above aim.max_speed ({})",
+                self.aim.min_speed, self.aim.max_speed
+            ));
+        }
+        Ok(())
+    }
+}
+
+struct TuningLoader;
+
+impl AssetLoader for TuningLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let tuning: Tuning = parse_ron_asset(load_context.path(), bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(tuning));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tuning.ron"]
+    }
+}
+
+fn apply_tuning(
+    mut events: EventReader<AssetEvent<Tuning>>,
+    files: Res<Assets<Tuning>>,
+    tuning_assets: Option<Res<TuningAssets>>,
+    mut tuning: ResMut<Tuning>,
+) {
+    let Some(tuning_assets) = tuning_assets else {
+        return;
+    };
+    let handle = &tuning_assets.tuning;
+    let modified = events.iter().any(
+        |event| matches!(event, AssetEvent::Modified { handle: modified } if modified == handle),
+    );
+
+    if tuning_assets.is_added() || modified {
+        if let Some(file) = files.get(handle) {
+            match file.check() {
+                Ok(()) => {
+                    info!("Applying tuning");
+                    *tuning = file.clone();
+                }
+                Err(problem) => warn!("Keeping the previous tuning: {}", problem),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_tuning_is_fine() {
+        assert_eq!(Tuning::default().check(), Ok(()));
+    }
+
+    #[test]
+    fn values_the_game_divides_by_are_rejected() {
+        let mut tuning = Tuning::default();
+        tuning.aim.full_drag = 0.;
+        assert!(tuning.check().unwrap_err().contains("aim.full_drag"));
+
+        let mut tuning = Tuning::default();
+        tuning.waves.first_interval = -1.;
+        assert!(tuning.check().unwrap_err().contains("waves.first_interval"));
+
+        let mut tuning = Tuning::default();
+        tuning.waves.min_interval = f32::NAN;
+        assert!(tuning.check().unwrap_err().contains("waves.min_interval"));
+
+        let mut tuning = Tuning::default();
+        tuning.aim.min_speed = tuning.aim.max_speed + 1.;
+        assert!(tuning.check().unwrap_err().contains("aim.min_speed"));
+    }
+}
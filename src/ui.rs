@@ -1,6 +1,8 @@
+use crate::cleanup::DespawnOnExit;
 use crate::loading::FontAssets;
 
-use crate::patient::PatientHealedEvent;
+use crate::patient::{LostPatients, PatientHealedEvent, PatientSpawnConfig, MAX_LOST_PATIENTS};
+use crate::score::Score;
 use crate::GameState;
 use bevy::prelude::*;
 
@@ -9,25 +11,33 @@ impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(setup_ui.in_schedule(OnEnter(GameState::Playing)))
             .add_system(update_ui.in_set(OnUpdate(GameState::Playing)))
-            .add_system(cleanup_ui.in_schedule(OnExit(GameState::Playing)));
+            .add_system(update_wave_text.in_set(OnUpdate(GameState::Playing)))
+            .add_system(spawn_heal_popups.in_set(OnUpdate(GameState::Playing)))
+            .add_system(float_heal_popups.in_set(OnUpdate(GameState::Playing)));
     }
 }
 
 #[derive(Component)]
-struct UiEntity;
+struct PatientTracker;
 
 #[derive(Component)]
-struct PatientTracker {
-    saved: i32,
+struct WaveText;
+
+/// Short text that floats up from where a patient was healed
+#[derive(Component)]
+struct HealPopup {
+    timer: Timer,
 }
 
+const HEAL_POPUP_SPEED: f32 = 40.;
+
 fn setup_ui(mut commands: Commands, font_assets: Res<FontAssets>) {
     commands
-        .spawn(UiEntity)
-        .insert(PatientTracker { saved: 0 })
+        .spawn(DespawnOnExit(GameState::Playing))
+        .insert(PatientTracker)
         .insert(
             TextBundle::from_section(
-                "Patients saved: 0",
+                tracker_text(&Score::default(), 0),
                 TextStyle {
                     font: font_assets.fira_sans.clone(),
                     font_size: 30.0,
@@ -46,7 +56,7 @@ fn setup_ui(mut commands: Commands, font_assets: Res<FontAssets>) {
             }),
         );
 
-    commands.spawn(UiEntity).insert(
+    commands.spawn(DespawnOnExit(GameState::Playing)).insert(
         TextBundle::from_section(
             "Tap the beakers to pop out pills.\nSave the patients!",
             TextStyle {
@@ -66,26 +76,103 @@ fn setup_ui(mut commands: Commands, font_assets: Res<FontAssets>) {
             ..default()
         }),
     );
+    commands
+        .spawn(DespawnOnExit(GameState::Playing))
+        .insert(WaveText)
+        .insert(
+            TextBundle::from_section(
+                "Wave 1",
+                TextStyle {
+                    font: font_assets.fira_sans.clone(),
+                    font_size: 30.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(15.0),
+                    left: Val::Px(100.0),
+                    ..default()
+                },
+                ..default()
+            }),
+        );
 }
 
 fn update_ui(
-    mut query: Query<(&mut PatientTracker, &mut Text)>,
-    mut events: EventReader<PatientHealedEvent>,
+    score: Res<Score>,
+    lost: Res<LostPatients>,
+    mut query: Query<&mut Text, With<PatientTracker>>,
+) {
+    if score.is_changed() || lost.is_changed() {
+        query.single_mut().sections[0].value = tracker_text(&score, lost.0);
+    }
+}
+
+fn tracker_text(score: &Score, lost: u32) -> String {
+    format!(
+        "Score: {} (x{:.1})\nPatients saved: {}\nPatients lost: {}/{}",
+        score.points,
+        score.multiplier(),
+        score.saved,
+        lost,
+        MAX_LOST_PATIENTS
+    )
+}
+
+fn update_wave_text(
+    config: Res<PatientSpawnConfig>,
+    mut shown_wave: Local<u32>,
+    mut query: Query<&mut Text, With<WaveText>>,
 ) {
-    let (mut tracker, mut text) = query.single_mut();
-    let mut changed = false;
-    for _ev in events.iter() {
-        tracker.saved += 1;
-        changed = true;
+    // the spawn config changes every frame, so only touch the text when the wave does
+    if *shown_wave != config.wave_number {
+        *shown_wave = config.wave_number;
+        query.single_mut().sections[0].value = format!("Wave {}", config.wave_number);
     }
+}
 
-    if changed {
-        text.sections[0].value = format!("Patients saved: {}", tracker.saved);
+fn spawn_heal_popups(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    mut events: EventReader<PatientHealedEvent>,
+) {
+    for ev in events.iter() {
+        commands
+            .spawn(DespawnOnExit(GameState::Playing))
+            .insert(HealPopup {
+                timer: Timer::from_seconds(1., TimerMode::Once),
+            })
+            .insert(Text2dBundle {
+                text: Text::from_section(
+                    format!("{:?} cured!", ev.ailment),
+                    TextStyle {
+                        font: font_assets.fira_sans.clone(),
+                        font_size: 24.0,
+                        color: ev.pill.def().color,
+                    },
+                ),
+                transform: Transform::from_translation(ev.position.extend(5.)),
+                ..default()
+            });
     }
 }
 
-fn cleanup_ui(mut commands: Commands, entities: Query<Entity, With<UiEntity>>) {
-    for e in entities.iter() {
-        commands.entity(e).despawn_recursive();
+fn float_heal_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut popups: Query<(Entity, &mut HealPopup, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut popup, mut transform, mut text) in popups.iter_mut() {
+        popup.timer.tick(time.delta());
+        transform.translation.y += HEAL_POPUP_SPEED * time.delta_seconds();
+        text.sections[0]
+            .style
+            .color
+            .set_a(popup.timer.percent_left());
+        if popup.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
     }
 }
@@ -0,0 +1,37 @@
+use crate::patient::Ailment;
+use crate::tuning::WaveTuning;
+use std::time::Duration;
+
+/// How many patients the first wave has, and how many more every wave after it brings
+const FIRST_WAVE_PATIENTS: u32 = 5;
+const EXTRA_PATIENTS_PER_WAVE: u32 = 3;
+
+/// One wave of patients. Waves get bigger, faster and more varied the further a run goes.
+pub struct Wave {
+    /// How many patients the wave spawns
+    pub patients: u32,
+    /// Time between two patients
+    pub interval: Duration,
+    /// The ailments patients of this wave can have
+    pub ailments: &'static [Ailment],
+    /// Break after the last patient of this wave, before the next wave starts
+    pub rest: Duration,
+}
+
+impl Wave {
+    /// The wave with the given number, starting at 1. Every wave after the first spawns a bit faster.
+    pub fn new(number: u32, tuning: &WaveTuning) -> Self {
+        let n = number.saturating_sub(1);
+        let interval = tuning.first_interval * tuning.interval_factor.powi(n as i32);
+
+        // start out with two ailments, then add one more per wave
+        let ailment_count = (2 + n as usize).min(Ailment::ALL.len());
+
+        Wave {
+            patients: FIRST_WAVE_PATIENTS + EXTRA_PATIENTS_PER_WAVE * n,
+            interval: Duration::from_secs_f32(interval.max(tuning.min_interval)),
+            ailments: &Ailment::ALL[..ailment_count],
+            rest: Duration::from_secs_f32(tuning.rest),
+        }
+    }
+}
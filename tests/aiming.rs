@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+use hospital_at_home::headless::Simulation;
+use hospital_at_home::{Pill, PointerPhase};
+
+/// The middle beaker on the left of the default level
+const LEFT_BEAKER: Vec2 = Vec2::new(-370., 0.);
+
+fn pill_velocities(sim: &mut Simulation) -> Vec<Vec2> {
+    let mut pills = sim.app.world.query_filtered::<&Velocity, With<Pill>>();
+    pills
+        .iter(&sim.app.world)
+        .map(|velocity| velocity.linvel)
+        .collect()
+}
+
+#[test]
+fn aimed_pills_leave_at_the_aimed_velocity() {
+    let mut sim = Simulation::new(7);
+    sim.hold_waves();
+
+    sim.aim(LEFT_BEAKER, Vec2::new(300., 200.));
+    sim.advance(0.2);
+
+    // however heavy the pill came out, only gravity slows it down
+    let velocities = pill_velocities(&mut sim);
+    assert_eq!(velocities.len(), 1);
+    assert!((velocities[0].x - 300.).abs() < 1., "{:?}", velocities[0]);
+    assert!(velocities[0].y < 200.);
+}
+
+#[test]
+fn aim_turns_the_beaker_only_so_far() {
+    let mut sim = Simulation::new(7);
+    sim.hold_waves();
+
+    // the beaker points 30 degrees right of up and turns 60 more at most, so it ends up pointing right
+    sim.aim(LEFT_BEAKER, Vec2::new(0., -400.));
+    sim.advance(0.2);
+
+    let velocities = pill_velocities(&mut sim);
+    assert_eq!(velocities.len(), 1);
+    assert!(velocities[0].x > 0., "{:?}", velocities[0]);
+}
+
+/// The middle left beaker's angle in the level
+const LEFT_BEAKER_ANGLE: f32 = -30.;
+
+/// Angle of a direction, in degrees counterclockwise from straight up
+fn degrees(dir: Vec2) -> f32 {
+    (-dir.x).atan2(dir.y).to_degrees()
+}
+
+#[test]
+fn dragging_aims_and_letting_go_fires_along_the_drag() {
+    let mut sim = Simulation::new(7);
+    sim.hold_waves();
+
+    // a sideways drag from the top of the beaker, which turns the beaker away from where it was pressed
+    let start = LEFT_BEAKER + Vec2::new(0., 30.);
+    let end = start + Vec2::new(100., 60.);
+    sim.point(PointerPhase::Press, start, start);
+    sim.point(PointerPhase::Drag, start, end);
+    sim.advance(0.1);
+    assert!((sim.beaker_angle(LEFT_BEAKER) - degrees(end - start)).abs() < 0.1);
+
+    sim.point(PointerPhase::Release, start, end);
+    sim.advance(0.05);
+
+    let velocities = pill_velocities(&mut sim);
+    assert_eq!(velocities.len(), 1);
+    // a 200 pixel drag launches at 700 pixels per second
+    assert!((velocities[0].x - 350.).abs() < 1., "{:?}", velocities[0]);
+    assert!(velocities[0].y > 0., "{:?}", velocities[0]);
+    assert!((sim.beaker_angle(LEFT_BEAKER) - LEFT_BEAKER_ANGLE).abs() < 0.1);
+}
+
+#[test]
+fn dragging_back_to_the_beaker_fires_a_plain_tap() {
+    let mut sim = Simulation::new(7);
+    sim.hold_waves();
+
+    sim.point(PointerPhase::Press, LEFT_BEAKER, LEFT_BEAKER);
+    sim.point(
+        PointerPhase::Drag,
+        LEFT_BEAKER,
+        LEFT_BEAKER + Vec2::new(100., 60.),
+    );
+    sim.advance(0.1);
+    let back = LEFT_BEAKER + Vec2::new(5., 0.);
+    sim.point(PointerPhase::Drag, LEFT_BEAKER, back);
+    sim.advance(0.1);
+    assert!((sim.beaker_angle(LEFT_BEAKER) - LEFT_BEAKER_ANGLE).abs() < 0.1);
+
+    sim.point(PointerPhase::Release, LEFT_BEAKER, back);
+    sim.advance(0.05);
+
+    // the pill leaves the way the beaker points at rest, bent only a little by gravity so far
+    let velocities = pill_velocities(&mut sim);
+    assert_eq!(velocities.len(), 1);
+    assert!(
+        (degrees(velocities[0]) - LEFT_BEAKER_ANGLE).abs() < 5.,
+        "{:?}",
+        velocities[0]
+    );
+    assert!((sim.beaker_angle(LEFT_BEAKER) - LEFT_BEAKER_ANGLE).abs() < 0.1);
+}
+
+#[test]
+fn cancelled_aims_fire_nothing() {
+    let mut sim = Simulation::new(7);
+    sim.hold_waves();
+
+    let end = LEFT_BEAKER + Vec2::new(100., 60.);
+    sim.point(PointerPhase::Press, LEFT_BEAKER, LEFT_BEAKER);
+    sim.point(PointerPhase::Drag, LEFT_BEAKER, end);
+    sim.advance(0.1);
+    sim.point(PointerPhase::Cancel, LEFT_BEAKER, end);
+    sim.advance(0.2);
+
+    assert!(pill_velocities(&mut sim).is_empty());
+    assert!((sim.beaker_angle(LEFT_BEAKER) - LEFT_BEAKER_ANGLE).abs() < 0.1);
+}
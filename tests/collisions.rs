@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use hospital_at_home::headless::{Simulation, PATIENT_RADIUS, PILL_RADIUS};
+use hospital_at_home::{Ailment, Health, Pill, PillKind};
+
+fn quiet_ward() -> Simulation {
+    let mut sim = Simulation::new(7);
+    sim.hold_waves();
+    sim
+}
+
+#[test]
+fn pill_heals_patient_once_and_both_disappear() {
+    let mut sim = quiet_ward();
+    let patient = sim.spawn_patient(Ailment::Fever, Vec2::ZERO);
+    let pill = sim.spawn_pill(PillKind::Heal, Vec2::new(-150., 0.), Vec2::new(500., 0.));
+
+    sim.advance(1.);
+
+    assert_eq!(sim.healed().len(), 1);
+    let heal = &sim.healed()[0];
+    assert_eq!(heal.patient, patient);
+    assert_eq!(heal.ailment, Ailment::Fever);
+    assert_eq!(heal.pill, PillKind::Heal);
+    assert!(!heal.bank_shot);
+    assert!(!sim.exists(patient));
+    assert!(!sim.exists(pill));
+}
+
+#[test]
+fn pills_bouncing_off_each_other_do_nothing() {
+    let mut sim = quiet_ward();
+    let left = sim.spawn_pill(PillKind::Heal, Vec2::new(-100., 0.), Vec2::new(400., 0.));
+    let right = sim.spawn_pill(PillKind::Heal, Vec2::new(100., 0.), Vec2::new(-400., 0.));
+
+    sim.advance(0.5);
+
+    assert!(sim.healed().is_empty());
+    for pill in [left, right] {
+        assert!(sim.exists(pill));
+        let pill = sim.app.world.get::<Pill>(pill).unwrap();
+        assert_eq!(pill.doses, 1);
+        assert_eq!(pill.heals, 0);
+        assert_eq!(pill.bounces, 1);
+    }
+}
+
+#[test]
+fn pill_bouncing_off_a_wall_does_nothing() {
+    let mut sim = quiet_ward();
+    let pill = sim.spawn_pill(PillKind::Heal, Vec2::new(-300., 0.), Vec2::new(-600., 0.));
+
+    sim.advance(0.5);
+
+    assert!(sim.healed().is_empty());
+    assert!(sim.exists(pill));
+    let pill = sim.app.world.get::<Pill>(pill).unwrap();
+    assert_eq!(pill.doses, 1);
+    assert_eq!(pill.bounces, 1);
+}
+
+#[test]
+fn pill_touching_two_patients_at_once_heals_only_one() {
+    let mut sim = quiet_ward();
+    // both patients overlap the pill from the first physics step on
+    let offset = PATIENT_RADIUS + PILL_RADIUS / 2.;
+    let left = sim.spawn_patient(Ailment::Fever, Vec2::new(-offset, 0.));
+    let right = sim.spawn_patient(Ailment::Fever, Vec2::new(offset, 0.));
+    let pill = sim.spawn_pill(PillKind::Heal, Vec2::ZERO, Vec2::ZERO);
+
+    sim.advance(0.1);
+
+    assert_eq!(sim.healed().len(), 1);
+    assert_eq!(sim.healed()[0].pill_heals, 1);
+    assert!(!sim.exists(pill));
+    assert_eq!(
+        [left, right]
+            .into_iter()
+            .filter(|&patient| sim.exists(patient))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn patient_healed_as_their_health_runs_out_is_only_healed() {
+    let mut sim = quiet_ward();
+    let patient = sim.spawn_patient(Ailment::Fever, Vec2::ZERO);
+    sim.app.world.get_mut::<Health>(patient).unwrap().0 = 0.01;
+    // the pill overlaps the patient from the first physics step on
+    sim.spawn_pill(PillKind::Heal, Vec2::new(PATIENT_RADIUS, 0.), Vec2::ZERO);
+
+    sim.advance(0.1);
+
+    assert_eq!(sim.healed().len(), 1);
+    assert_eq!(sim.report().lost, 0);
+    assert!(!sim.exists(patient));
+}
+
+#[test]
+fn heals_report_how_hard_the_pill_hit() {
+    let mut sim = quiet_ward();
+    sim.spawn_patient(Ailment::Fever, Vec2::ZERO);
+    sim.spawn_pill(PillKind::Heal, Vec2::new(-150., 0.), Vec2::new(500., 0.));
+
+    sim.advance(1.);
+
+    // the bounce would have slowed the pill down or sent it back, but the hit itself was head on
+    assert_eq!(sim.healed().len(), 1);
+    let hit = sim.healed()[0].relative_velocity;
+    assert!((hit.x - 500.).abs() < 1., "{:?}", hit);
+    assert!(hit.y.abs() < 1., "{:?}", hit);
+}
+
+#[test]
+fn pills_falling_through_a_dying_patient_do_not_bounce() {
+    let mut sim = quiet_ward();
+    let patient = sim.spawn_patient(Ailment::Fever, Vec2::ZERO);
+    sim.app.world.get_mut::<Health>(patient).unwrap().0 = 0.01;
+    sim.advance(0.05);
+    // a pill of the wrong kind, so it would only count as a bounce
+    let pill = sim.spawn_pill(PillKind::Grow, Vec2::new(-100., 0.), Vec2::new(500., 0.));
+
+    sim.advance(0.3);
+
+    assert!(sim.exists(pill));
+    assert_eq!(sim.app.world.get::<Pill>(pill).unwrap().bounces, 0);
+}
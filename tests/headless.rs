@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+use hospital_at_home::headless::{Report, Simulation};
+use hospital_at_home::Patient;
+
+#[test]
+fn runs_without_a_window() {
+    let mut sim = Simulation::new(42);
+    // bottom left beaker of the default 800x600 arena
+    sim.tap(Vec2::new(-370., -270.));
+    sim.advance(10.);
+    assert!(!sim.is_over());
+}
+
+#[test]
+fn bot_heals_patients() {
+    let mut sim = Simulation::new(42);
+    sim.autoplay();
+    sim.run_for(60.);
+    let report = sim.report();
+    assert!(report.pills_fired > 0);
+    assert!(report.healed > 0);
+}
+
+#[test]
+fn runs_that_have_not_started_heal_nobody_per_minute() {
+    let report = Report {
+        seconds: 0.,
+        pills_fired: 0,
+        healed: 0,
+        lost: 0,
+        score: 0,
+        wave: 1,
+    };
+    assert_eq!(report.heals_per_minute(), 0.);
+}
+
+/// A run on the given seed with the same few taps and one aimed shot every time
+fn tapped_run(seed: u64) -> Simulation {
+    let mut sim = Simulation::new(seed);
+    for (x, y) in [(-370., -270.), (370., -270.), (-370., 270.), (370., 270.)] {
+        sim.tap(Vec2::new(x, y));
+        sim.advance(2.);
+    }
+    sim.aim(Vec2::new(-370., -270.), Vec2::new(300., 500.));
+    sim.advance(5.);
+    sim
+}
+
+/// Where every patient is, in the order they spawned
+fn patient_positions(sim: &mut Simulation) -> Vec<(Entity, Vec3)> {
+    let mut patients = sim
+        .app
+        .world
+        .query_filtered::<(Entity, &Transform), With<Patient>>();
+    let mut positions: Vec<_> = patients
+        .iter(&sim.app.world)
+        .map(|(entity, transform)| (entity, transform.translation))
+        .collect();
+    positions.sort_by_key(|(entity, _)| *entity);
+    positions
+}
+
+#[test]
+fn same_seed_and_taps_play_out_the_same() {
+    let mut first = tapped_run(11);
+    let mut second = tapped_run(11);
+
+    assert!(first.report().pills_fired > 0);
+    assert_eq!(first.report(), second.report());
+    let positions = patient_positions(&mut first);
+    assert!(!positions.is_empty());
+    assert_eq!(positions, patient_positions(&mut second));
+}